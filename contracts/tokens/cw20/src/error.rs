@@ -0,0 +1,98 @@
+use cosmwasm_std::{Decimal, StdError, Uint128};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Base(#[from] cw20_base::ContractError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Address is on the deny-list: {address}")]
+    Denied { address: String },
+
+    #[error("Transfer rejected by the compliance contract: {reason}")]
+    ComplianceRejected { reason: String },
+
+    #[error("Contract is not an allowed receiver: {contract}")]
+    ReceiverNotAllowed { contract: String },
+
+    #[error("Every holder is already indexed")]
+    HoldersIndexed {},
+
+    #[error("Token is paused")]
+    Paused {},
+
+    #[error("Account is frozen: {address}")]
+    Frozen { address: String },
+
+    #[error("Invalid permit signature")]
+    InvalidSignature {},
+
+    #[error("Permit public key does not match the owner {owner}")]
+    PubkeyMismatch { owner: String },
+
+    #[error("Invalid permit nonce: expected {expected}, got {got}")]
+    InvalidNonce { expected: u64, got: u64 },
+
+    #[error("Invalid transfer tax: {0}")]
+    InvalidTax(String),
+
+    #[error("Invalid emission schedule: {0}")]
+    InvalidEmission(String),
+
+    #[error("Mint exceeds the emission limits, mintable now: {mintable}")]
+    MintLimitExceeded { mintable: Uint128 },
+
+    #[error("Invalid vesting: {0}")]
+    InvalidVesting(String),
+
+    #[error("No vested tokens to claim")]
+    NothingToClaim {},
+
+    #[error("Vesting of {address} cannot be revoked")]
+    NotRevocable { address: String },
+
+    #[error("Mint exceeds the quota of the minter, remaining: {remaining}")]
+    QuotaExceeded { remaining: Uint128 },
+
+    #[error("Token is not in rebasing mode")]
+    NotRebasing {},
+
+    #[error("Rebase index cannot be zero")]
+    ZeroIndex {},
+
+    #[error("Rebase index must stay between {min} and {max}")]
+    RebaseOutOfBounds { min: Decimal, max: Decimal },
+
+    #[error("Batch cannot be empty")]
+    EmptyBatch {},
+
+    #[error("Batch cannot hold more than {max} legs")]
+    BatchTooLarge { max: usize },
+
+    #[error("Insufficient balance: {balance}, required: {required}")]
+    InsufficientBalance { balance: Uint128, required: Uint128 },
+
+    #[error("Cannot migrate from {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot migrate from version {previous_version} to {new_version}")]
+    CannotMigrateVersion {
+        previous_version: String,
+        new_version: String,
+    },
+
+    #[error("Semver parsing error: {0}")]
+    SemVer(String),
+}
+
+impl From<semver::Error> for ContractError {
+    fn from(err: semver::Error) -> Self {
+        Self::SemVer(err.to_string())
+    }
+}
@@ -1,64 +1,1213 @@
+use crate::error::ContractError;
+use crate::helpers::{
+    assert_allowed_receiver, assert_compliant, assert_not_paused, calculate_tax, locked,
+    only_compliance_admin, only_minter, only_minter_admin, only_pauser, pubkey_to_address,
+    record_mint, seed_snapshots, sync_balances, to_amount, to_shares, validate_tax, vested, Tax,
+};
+use crate::msg::{ExecuteMsg, PermitPayload, SendLeg, TransferLeg};
+use crate::state::{
+    MinterQuota, ALLOWED_RECEIVERS, COMPLIANCE, DENY_LIST, FROZEN, HOLDER_BACKFILL, MINTERS, PAUSE,
+    PERMIT_NONCES, REBASE_INDEX, TAX, TAX_EXEMPT, VESTING, VESTING_ADMIN,
+};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{
+    to_vec, Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, Event, MessageInfo, Order, Response,
+    StdError, StdResult, Storage, SubMsg, Uint128,
+};
 use cw20_base::allowances::{
-    execute_burn_from, execute_decrease_allowance, execute_increase_allowance, execute_send_from,
-    execute_transfer_from,
+    deduct_allowance, execute_burn_from, execute_decrease_allowance, execute_increase_allowance,
+    execute_send_from, execute_transfer_from,
 };
 use cw20_base::contract::{
     execute_burn, execute_mint, execute_send, execute_transfer, execute_update_marketing,
     execute_update_minter, execute_upload_logo,
 };
-use cw20_base::msg::ExecuteMsg;
+use cw20_base::state::{ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, TOKEN_INFO};
+use cw20_spec::{AllowanceResponse, Cw20ReceiveMsg, Expiration};
+use cw_storage_plus::Bound;
+use sha2::{Digest, Sha256};
+
+/// Maximum number of legs in a batch transfer or send
+pub const MAX_BATCH_SIZE: usize = 50;
+
+/// A rebase can at most divide or multiply the index by this factor
+pub const MAX_REBASE_FACTOR: u128 = 2;
+
+const MAX_INDEX_LIMIT: u32 = 100;
+const DEFAULT_INDEX_LIMIT: u32 = 30;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> Result<Response, cw20_base::ContractError> {
+) -> Result<Response, ContractError> {
+    // In rebasing mode cw20-base only ever sees shares
+    let index = REBASE_INDEX.may_load(deps.storage)?;
+    let msg = match index {
+        Some(index) => msg_to_shares(msg, index),
+        None => msg,
+    };
+
+    // Moving tokens is halted while paused, queries and allowances keep working
+    if let Some(accounts) = moved_accounts(&info, &msg) {
+        let accounts: Vec<&str> = accounts.iter().map(String::as_str).collect();
+        assert_not_paused(deps.as_ref(), &accounts)?;
+        seed_snapshots(deps.branch(), &accounts)?;
+    }
+
     match msg {
         ExecuteMsg::Transfer { recipient, amount } => {
-            execute_transfer(deps, env, info, recipient, amount)
+            wrapped_transfer(deps, &env, &info, recipient, amount)
+        }
+        ExecuteMsg::Burn { amount } => {
+            let res = execute_burn(deps.branch(), env.clone(), info.clone(), amount)?;
+            sync_balances(deps, &env, &[info.sender.as_str()])?;
+            Ok(res)
         }
-        ExecuteMsg::Burn { amount } => execute_burn(deps, env, info, amount),
         ExecuteMsg::Send {
             contract,
             amount,
             msg,
-        } => execute_send(deps, env, info, contract, amount, msg),
-        ExecuteMsg::Mint { recipient, amount } => execute_mint(deps, env, info, recipient, amount),
+        } => wrapped_send(deps, &env, &info, contract, amount, msg),
+        ExecuteMsg::BatchTransfer { transfers } => {
+            execute_batch_transfer(deps, env, info, transfers)
+        }
+        ExecuteMsg::BatchSend { sends } => execute_batch_send(deps, env, info, sends),
+        ExecuteMsg::Mint { recipient, amount } => {
+            assert_compliant(
+                deps.as_ref(),
+                &info.sender,
+                info.sender.as_str(),
+                &recipient,
+                amount,
+            )?;
+            // Quota minters are charged in tokens, then mint under the cw20-base minter
+            let quota = if MINTERS.has(deps.storage, &info.sender) {
+                Some(consume_quota(
+                    deps.storage,
+                    &info.sender,
+                    to_amount(amount, index),
+                )?)
+            } else {
+                None
+            };
+            let minter_info = match &quota {
+                Some(_) => MessageInfo {
+                    sender: base_minter(deps.as_ref())?,
+                    funds: vec![],
+                },
+                None => info,
+            };
+            let mut res = with_token_cap(deps.branch(), index, |deps| {
+                execute_mint(deps, env.clone(), minter_info, recipient.clone(), amount)
+            })?;
+            if let Some(quota) = quota {
+                res = res.add_attribute("remaining_quota", quota.remaining);
+            }
+            record_mint(deps.storage, &env, to_amount(amount, index))?;
+            sync_balances(deps, &env, &[&recipient])?;
+            Ok(res)
+        }
         ExecuteMsg::IncreaseAllowance {
             spender,
             amount,
             expires,
-        } => execute_increase_allowance(deps, env, info, spender, amount, expires),
+        } => Ok(execute_increase_allowance(
+            deps, env, info, spender, amount, expires,
+        )?),
         ExecuteMsg::DecreaseAllowance {
             spender,
             amount,
             expires,
-        } => execute_decrease_allowance(deps, env, info, spender, amount, expires),
+        } => Ok(execute_decrease_allowance(
+            deps, env, info, spender, amount, expires,
+        )?),
         ExecuteMsg::TransferFrom {
             owner,
             recipient,
             amount,
-        } => execute_transfer_from(deps, env, info, owner, recipient, amount),
-        ExecuteMsg::BurnFrom { owner, amount } => execute_burn_from(deps, env, info, owner, amount),
+        } => {
+            assert_compliant(deps.as_ref(), &info.sender, &owner, &recipient, amount)?;
+            let tax = calculate_tax(deps.as_ref(), &owner, &recipient, amount)?;
+            let shares = net_amount(amount, &tax);
+            let mut res =
+                with_token_allowance(deps.branch(), &env, &owner, &info.sender, shares, |deps| {
+                    execute_transfer_from(
+                        deps,
+                        env.clone(),
+                        info.clone(),
+                        owner.clone(),
+                        recipient.clone(),
+                        shares,
+                    )
+                })?;
+            if let Some(tax) = tax {
+                res = charge_tax(deps.branch(), &env, &info, Some(&owner), tax, res)?;
+            }
+            sync_balances(deps, &env, &[&owner, &recipient])?;
+            Ok(res)
+        }
+        ExecuteMsg::BurnFrom { owner, amount } => {
+            let res =
+                with_token_allowance(deps.branch(), &env, &owner, &info.sender, amount, |deps| {
+                    execute_burn_from(deps, env.clone(), info.clone(), owner.clone(), amount)
+                })?;
+            sync_balances(deps, &env, &[&owner])?;
+            Ok(res)
+        }
         ExecuteMsg::SendFrom {
             owner,
             contract,
             amount,
             msg,
-        } => execute_send_from(deps, env, info, owner, contract, amount, msg),
+        } => {
+            assert_allowed_receiver(deps.as_ref(), &contract)?;
+            assert_compliant(deps.as_ref(), &info.sender, &owner, &contract, amount)?;
+            let tax = calculate_tax(deps.as_ref(), &owner, &contract, amount)?;
+            let shares = net_amount(amount, &tax);
+            let mut res =
+                with_token_allowance(deps.branch(), &env, &owner, &info.sender, shares, |deps| {
+                    execute_send_from(
+                        deps,
+                        env.clone(),
+                        info.clone(),
+                        owner.clone(),
+                        contract.clone(),
+                        shares,
+                        msg.clone(),
+                    )
+                })?;
+            res = receive_in_amounts(deps.as_ref(), res, &info, &contract, shares, msg)?;
+            if let Some(tax) = tax {
+                res = charge_tax(deps.branch(), &env, &info, Some(&owner), tax, res)?;
+            }
+            sync_balances(deps, &env, &[&owner, &contract])?;
+            Ok(res)
+        }
         ExecuteMsg::UpdateMarketing {
             project,
             description,
             marketing,
-        } => execute_update_marketing(deps, env, info, project, description, marketing),
-        ExecuteMsg::UploadLogo(logo) => execute_upload_logo(deps, env, info, logo),
+        } => Ok(execute_update_marketing(
+            deps,
+            env,
+            info,
+            project,
+            description,
+            marketing,
+        )?),
+        ExecuteMsg::UploadLogo(logo) => Ok(execute_upload_logo(deps, env, info, logo)?),
         ExecuteMsg::UpdateMinter { new_minter } => {
-            execute_update_minter(deps, env, info, new_minter)
+            Ok(execute_update_minter(deps, env, info, new_minter)?)
+        }
+        ExecuteMsg::AddToDenyList { address } => execute_add_to_deny_list(deps, env, info, address),
+        ExecuteMsg::RemoveFromDenyList { address } => {
+            execute_remove_from_deny_list(deps, env, info, address)
+        }
+        ExecuteMsg::UpdateComplianceContract { contract } => {
+            execute_update_compliance_contract(deps, env, info, contract)
+        }
+        ExecuteMsg::UpdateComplianceAdmin { admin } => {
+            execute_update_compliance_admin(deps, env, info, admin)
+        }
+        ExecuteMsg::SetReceiverAllowlist { enabled } => {
+            execute_set_receiver_allowlist(deps, env, info, enabled)
+        }
+        ExecuteMsg::AddAllowedReceiver { address } => {
+            execute_add_allowed_receiver(deps, env, info, address)
+        }
+        ExecuteMsg::RemoveAllowedReceiver { address } => {
+            execute_remove_allowed_receiver(deps, env, info, address)
         }
+        ExecuteMsg::UpdateTax {
+            tax_bps,
+            tax_collector,
+        } => execute_update_tax(deps, env, info, tax_bps, tax_collector),
+        ExecuteMsg::SetTaxExemption { address, exempt } => {
+            execute_set_tax_exemption(deps, env, info, address, exempt)
+        }
+        ExecuteMsg::Rebase { new_index } => execute_rebase(deps, env, info, new_index),
+        ExecuteMsg::AddMinter { address, quota } => {
+            execute_add_minter(deps, env, info, address, quota)
+        }
+        ExecuteMsg::RemoveMinter { address } => execute_remove_minter(deps, env, info, address),
+        ExecuteMsg::SetQuota { address, quota } => {
+            execute_set_quota(deps, env, info, address, quota)
+        }
+        ExecuteMsg::RevokeVesting { address } => execute_revoke_vesting(deps, env, info, address),
+        ExecuteMsg::ClaimVesting {} => execute_claim_vesting(deps, env, info),
+        ExecuteMsg::IndexHolders { limit } => execute_index_holders(deps, env, info, limit),
+        ExecuteMsg::Pause {} => execute_set_paused(deps, env, info, true),
+        ExecuteMsg::Unpause {} => execute_set_paused(deps, env, info, false),
+        ExecuteMsg::FreezeAccount { address } => execute_set_frozen(deps, env, info, address, true),
+        ExecuteMsg::UnfreezeAccount { address } => {
+            execute_set_frozen(deps, env, info, address, false)
+        }
+        ExecuteMsg::Permit {
+            owner,
+            spender,
+            amount,
+            expires,
+            nonce,
+            signature,
+            pubkey,
+        } => execute_permit(
+            deps, env, info, owner, spender, amount, expires, nonce, signature, pubkey,
+        ),
+    }
+}
+
+/// The accounts whose tokens move with the message, `None` if no token moves
+fn moved_accounts(info: &MessageInfo, msg: &ExecuteMsg) -> Option<Vec<String>> {
+    let sender = info.sender.to_string();
+    let accounts = match msg {
+        ExecuteMsg::Transfer { recipient, .. } => vec![sender, recipient.clone()],
+        ExecuteMsg::Send { contract, .. } => vec![sender, contract.clone()],
+        ExecuteMsg::Burn { .. } => vec![sender],
+        ExecuteMsg::Mint { recipient, .. } => vec![sender, recipient.clone()],
+        ExecuteMsg::TransferFrom {
+            owner, recipient, ..
+        } => vec![sender, owner.clone(), recipient.clone()],
+        ExecuteMsg::SendFrom {
+            owner, contract, ..
+        } => vec![sender, owner.clone(), contract.clone()],
+        ExecuteMsg::BurnFrom { owner, .. } => vec![sender, owner.clone()],
+        ExecuteMsg::BatchTransfer { transfers } => std::iter::once(sender)
+            .chain(transfers.iter().map(|leg| leg.recipient.clone()))
+            .collect(),
+        ExecuteMsg::BatchSend { sends } => std::iter::once(sender)
+            .chain(sends.iter().map(|leg| leg.contract.clone()))
+            .collect(),
+        ExecuteMsg::RevokeVesting { address } => vec![sender, address.clone()],
+        ExecuteMsg::ClaimVesting {} => vec![sender],
+        // A rebase changes every balance at once, only the global pause applies
+        ExecuteMsg::Rebase { .. } => vec![],
+        _ => return None,
+    };
+
+    Some(accounts)
+}
+
+/// Converts the token amounts of a message into shares, rounding down. Allowances and
+/// permits stay in tokens
+fn msg_to_shares(msg: ExecuteMsg, index: Decimal) -> ExecuteMsg {
+    let shares = |amount| to_shares(amount, Some(index));
+    match msg {
+        ExecuteMsg::Transfer { recipient, amount } => ExecuteMsg::Transfer {
+            recipient,
+            amount: shares(amount),
+        },
+        ExecuteMsg::Burn { amount } => ExecuteMsg::Burn {
+            amount: shares(amount),
+        },
+        ExecuteMsg::Send {
+            contract,
+            amount,
+            msg,
+        } => ExecuteMsg::Send {
+            contract,
+            amount: shares(amount),
+            msg,
+        },
+        ExecuteMsg::TransferFrom {
+            owner,
+            recipient,
+            amount,
+        } => ExecuteMsg::TransferFrom {
+            owner,
+            recipient,
+            amount: shares(amount),
+        },
+        ExecuteMsg::SendFrom {
+            owner,
+            contract,
+            amount,
+            msg,
+        } => ExecuteMsg::SendFrom {
+            owner,
+            contract,
+            amount: shares(amount),
+            msg,
+        },
+        ExecuteMsg::BurnFrom { owner, amount } => ExecuteMsg::BurnFrom {
+            owner,
+            amount: shares(amount),
+        },
+        ExecuteMsg::Mint { recipient, amount } => ExecuteMsg::Mint {
+            recipient,
+            amount: shares(amount),
+        },
+        ExecuteMsg::BatchTransfer { transfers } => ExecuteMsg::BatchTransfer {
+            transfers: transfers
+                .into_iter()
+                .map(|leg| TransferLeg {
+                    amount: shares(leg.amount),
+                    ..leg
+                })
+                .collect(),
+        },
+        ExecuteMsg::BatchSend { sends } => ExecuteMsg::BatchSend {
+            sends: sends
+                .into_iter()
+                .map(|leg| SendLeg {
+                    amount: shares(leg.amount),
+                    ..leg
+                })
+                .collect(),
+        },
+        msg => msg,
+    }
+}
+
+/// In rebasing mode, notifies the receiver of a send in tokens rather than in shares
+fn receive_in_amounts(
+    deps: Deps,
+    mut res: Response,
+    info: &MessageInfo,
+    contract: &str,
+    shares: Uint128,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let index = REBASE_INDEX.may_load(deps.storage)?;
+    if index.is_some() {
+        let receive = Cw20ReceiveMsg {
+            sender: info.sender.to_string(),
+            amount: to_amount(shares, index),
+            msg,
+        };
+        res.messages = vec![SubMsg::new(receive.into_cosmos_msg(contract)?)];
+    }
+
+    Ok(res)
+}
+
+/// Transfer with the compliance checks, the tax and the snapshots applied
+fn wrapped_transfer(
+    mut deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_compliant(
+        deps.as_ref(),
+        &info.sender,
+        info.sender.as_str(),
+        &recipient,
+        amount,
+    )?;
+    let tax = calculate_tax(deps.as_ref(), info.sender.as_str(), &recipient, amount)?;
+    let mut res = execute_transfer(
+        deps.branch(),
+        env.clone(),
+        info.clone(),
+        recipient.clone(),
+        net_amount(amount, &tax),
+    )?;
+    if let Some(tax) = tax {
+        res = charge_tax(deps.branch(), env, info, None, tax, res)?;
+    }
+    sync_balances(deps, env, &[info.sender.as_str(), &recipient])?;
+
+    Ok(res)
+}
+
+/// Send with the compliance checks, the tax and the snapshots applied
+fn wrapped_send(
+    mut deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    assert_allowed_receiver(deps.as_ref(), &contract)?;
+    assert_compliant(
+        deps.as_ref(),
+        &info.sender,
+        info.sender.as_str(),
+        &contract,
+        amount,
+    )?;
+    // The receiver is notified of the amount it actually gets
+    let tax = calculate_tax(deps.as_ref(), info.sender.as_str(), &contract, amount)?;
+    let shares = net_amount(amount, &tax);
+    let mut res = execute_send(
+        deps.branch(),
+        env.clone(),
+        info.clone(),
+        contract.clone(),
+        shares,
+        msg.clone(),
+    )?;
+    res = receive_in_amounts(deps.as_ref(), res, info, &contract, shares, msg)?;
+    if let Some(tax) = tax {
+        res = charge_tax(deps.branch(), env, info, None, tax, res)?;
+    }
+    sync_balances(deps, env, &[info.sender.as_str(), &contract])?;
+
+    Ok(res)
+}
+
+/// Checks the size of a batch and that the sender can pay for all of it
+fn assert_batch(
+    deps: Deps,
+    sender: &Addr,
+    amounts: impl Iterator<Item = Uint128>,
+    len: usize,
+) -> Result<Uint128, ContractError> {
+    if len == 0 {
+        return Err(ContractError::EmptyBatch {});
+    }
+    if len > MAX_BATCH_SIZE {
+        return Err(ContractError::BatchTooLarge {
+            max: MAX_BATCH_SIZE,
+        });
+    }
+
+    let mut total = Uint128::zero();
+    for amount in amounts {
+        total = total.checked_add(amount).map_err(StdError::from)?;
+    }
+    let balance = BALANCES.may_load(deps.storage, sender)?.unwrap_or_default();
+    if total > balance {
+        return Err(ContractError::InsufficientBalance {
+            balance,
+            required: total,
+        });
+    }
+
+    Ok(total)
+}
+
+/// Turns the response of one leg of a batch into an event
+fn leg_event(ty: &str, res: Response) -> Event {
+    Event::new(ty).add_attributes(
+        res.attributes
+            .into_iter()
+            .filter(|attr| attr.key != "action"),
+    )
+}
+
+/// Transfer to several recipients at once
+pub fn execute_batch_transfer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    transfers: Vec<TransferLeg>,
+) -> Result<Response, ContractError> {
+    let total = assert_batch(
+        deps.as_ref(),
+        &info.sender,
+        transfers.iter().map(|leg| leg.amount),
+        transfers.len(),
+    )?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "batch_transfer")
+        .add_attribute("from", &info.sender)
+        .add_attribute("count", transfers.len().to_string())
+        .add_attribute("total", total);
+    for leg in transfers {
+        let leg_res = wrapped_transfer(deps.branch(), &env, &info, leg.recipient, leg.amount)?;
+        res = res.add_event(leg_event("transfer", leg_res));
+    }
+
+    Ok(res)
+}
+
+/// Send to several contracts at once, each contract receives its own hook
+pub fn execute_batch_send(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sends: Vec<SendLeg>,
+) -> Result<Response, ContractError> {
+    let total = assert_batch(
+        deps.as_ref(),
+        &info.sender,
+        sends.iter().map(|leg| leg.amount),
+        sends.len(),
+    )?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "batch_send")
+        .add_attribute("from", &info.sender)
+        .add_attribute("count", sends.len().to_string())
+        .add_attribute("total", total);
+    for leg in sends {
+        let mut leg_res = wrapped_send(
+            deps.branch(),
+            &env,
+            &info,
+            leg.contract,
+            leg.amount,
+            leg.msg,
+        )?;
+        res = res.add_submessages(std::mem::take(&mut leg_res.messages));
+        res = res.add_event(leg_event("send", leg_res));
     }
+
+    Ok(res)
+}
+
+/// The amount delivered to the recipient once the tax is deducted
+fn net_amount(amount: Uint128, tax: &Option<Tax>) -> Uint128 {
+    tax.as_ref()
+        .map_or(amount, |tax| amount.saturating_sub(tax.amount))
+}
+
+/// Credits the tax to the collector, out of the allowance of the sender when `owner` is set.
+/// The collector is checked like any other recipient
+fn charge_tax(
+    mut deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    owner: Option<&str>,
+    tax: Tax,
+    res: Response,
+) -> Result<Response, ContractError> {
+    let collector = tax.collector.to_string();
+    let from = owner.unwrap_or(info.sender.as_str());
+    assert_not_paused(deps.as_ref(), &[&collector])?;
+    assert_compliant(deps.as_ref(), &info.sender, from, &collector, tax.amount)?;
+    seed_snapshots(deps.branch(), &[&collector])?;
+    match owner {
+        Some(owner) => with_token_allowance(
+            deps.branch(),
+            env,
+            owner,
+            &info.sender,
+            tax.amount,
+            |deps| {
+                execute_transfer_from(
+                    deps,
+                    env.clone(),
+                    info.clone(),
+                    owner.to_string(),
+                    collector.clone(),
+                    tax.amount,
+                )
+            },
+        )?,
+        None => execute_transfer(
+            deps.branch(),
+            env.clone(),
+            info.clone(),
+            collector.clone(),
+            tax.amount,
+        )?,
+    };
+    sync_balances(deps, env, &[&collector])?;
+
+    Ok(res
+        .add_attribute("tax", tax.amount)
+        .add_attribute("tax_collector", collector))
+}
+
+/// Update the transfer tax
+pub fn execute_update_tax(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    tax_bps: u16,
+    tax_collector: Option<String>,
+) -> Result<Response, ContractError> {
+    only_minter(deps.as_ref(), &info.sender)?;
+
+    let tax = validate_tax(deps.as_ref(), tax_bps, tax_collector.as_deref())?;
+    TAX.save(deps.storage, &tax)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_tax")
+        .add_attribute("tax_bps", tax_bps.to_string())
+        .add_attribute(
+            "tax_collector",
+            tax_collector.unwrap_or_else(|| "none".to_string()),
+        ))
+}
+
+/// Exempt an address from the transfer tax or remove its exemption
+pub fn execute_set_tax_exemption(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    exempt: bool,
+) -> Result<Response, ContractError> {
+    only_minter(deps.as_ref(), &info.sender)?;
+
+    let addr = deps.api.addr_validate(&address)?;
+    if exempt {
+        TAX_EXEMPT.save(deps.storage, &addr, &Empty {})?;
+    } else {
+        TAX_EXEMPT.remove(deps.storage, &addr);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_tax_exemption")
+        .add_attribute("address", address)
+        .add_attribute("exempt", exempt.to_string()))
+}
+
+/// Change the amount of tokens per share, within `MAX_REBASE_FACTOR` of the current index
+pub fn execute_rebase(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_index: Decimal,
+) -> Result<Response, ContractError> {
+    only_minter(deps.as_ref(), &info.sender)?;
+
+    let index = REBASE_INDEX
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotRebasing {})?;
+    if new_index.is_zero() {
+        return Err(ContractError::ZeroIndex {});
+    }
+    let min = index * Decimal::from_ratio(1u128, MAX_REBASE_FACTOR);
+    let max = index
+        .checked_mul(Decimal::from_ratio(MAX_REBASE_FACTOR, 1u128))
+        .map_err(StdError::from)?;
+    if new_index < min || new_index > max {
+        return Err(ContractError::RebaseOutOfBounds { min, max });
+    }
+    REBASE_INDEX.save(deps.storage, &new_index, env.block.height)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "rebase")
+        .add_attribute("old_index", index.to_string())
+        .add_attribute("new_index", new_index.to_string()))
+}
+
+/// Runs a cw20-base `*_from` operation moving `shares` while the allowance is kept in tokens.
+/// The allowance is charged in tokens, cw20-base then spends exactly `shares` from it
+fn with_token_allowance(
+    mut deps: DepsMut,
+    env: &Env,
+    owner: &str,
+    spender: &Addr,
+    shares: Uint128,
+    operation: impl FnOnce(DepsMut) -> Result<Response, cw20_base::ContractError>,
+) -> Result<Response, ContractError> {
+    let index = match REBASE_INDEX.may_load(deps.storage)? {
+        Some(index) => index,
+        None => return Ok(operation(deps)?),
+    };
+
+    let owner = deps.api.addr_validate(owner)?;
+    let remaining = deduct_allowance(
+        deps.storage,
+        &owner,
+        spender,
+        &env.block,
+        to_amount(shares, Some(index)),
+    )?;
+    let spendable = AllowanceResponse {
+        allowance: shares,
+        expires: remaining.expires,
+    };
+    ALLOWANCES.save(deps.storage, (&owner, spender), &spendable)?;
+    ALLOWANCES_SPENDER.save(deps.storage, (spender, &owner), &spendable)?;
+
+    let res = operation(deps.branch())?;
+    ALLOWANCES.save(deps.storage, (&owner, spender), &remaining)?;
+    ALLOWANCES_SPENDER.save(deps.storage, (spender, &owner), &remaining)?;
+
+    Ok(res)
+}
+
+/// Runs a cw20-base mint while the cap is kept in tokens, cw20-base checks it in shares
+fn with_token_cap(
+    mut deps: DepsMut,
+    index: Option<Decimal>,
+    operation: impl FnOnce(DepsMut) -> Result<Response, cw20_base::ContractError>,
+) -> Result<Response, ContractError> {
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    let cap = match (index, config.get_cap()) {
+        (Some(_), Some(cap)) => cap,
+        _ => return Ok(operation(deps)?),
+    };
+
+    if let Some(mint) = config.mint.as_mut() {
+        mint.cap = Some(to_shares(cap, index));
+    }
+    TOKEN_INFO.save(deps.storage, &config)?;
+    let res = operation(deps.branch())?;
+    TOKEN_INFO.update(deps.storage, |mut config| -> StdResult<_> {
+        if let Some(mint) = config.mint.as_mut() {
+            mint.cap = Some(cap);
+        }
+        Ok(config)
+    })?;
+
+    Ok(res)
+}
+
+/// Charges a mint of `amount` tokens to the quota of `minter`
+fn consume_quota(
+    storage: &mut dyn Storage,
+    minter: &Addr,
+    amount: Uint128,
+) -> Result<MinterQuota, ContractError> {
+    let mut quota = MINTERS.load(storage, minter)?;
+    if amount > quota.remaining {
+        return Err(ContractError::QuotaExceeded {
+            remaining: quota.remaining,
+        });
+    }
+    quota.remaining -= amount;
+    quota.minted += amount;
+    MINTERS.save(storage, minter, &quota)?;
+
+    Ok(quota)
+}
+
+/// The cw20-base minter, quota minters mint under it so its cap still applies
+fn base_minter(deps: Deps) -> Result<Addr, ContractError> {
+    TOKEN_INFO
+        .load(deps.storage)?
+        .mint
+        .map(|mint| mint.minter)
+        .ok_or_else(|| cw20_base::ContractError::Unauthorized {}.into())
+}
+
+/// Allow an address to mint up to `quota`
+pub fn execute_add_minter(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    quota: Uint128,
+) -> Result<Response, ContractError> {
+    only_minter_admin(deps.as_ref(), &info.sender)?;
+
+    let minter = deps.api.addr_validate(&address)?;
+    let quota = MinterQuota {
+        remaining: quota,
+        minted: Uint128::zero(),
+    };
+    MINTERS.save(deps.storage, &minter, &quota)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_minter")
+        .add_attribute("address", address)
+        .add_attribute("quota", quota.remaining))
+}
+
+/// Remove a minter
+pub fn execute_remove_minter(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    only_minter_admin(deps.as_ref(), &info.sender)?;
+
+    let minter = deps.api.addr_validate(&address)?;
+    MINTERS.remove(deps.storage, &minter);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_minter")
+        .add_attribute("address", address))
+}
+
+/// Reset the amount a minter can still mint
+pub fn execute_set_quota(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    quota: Uint128,
+) -> Result<Response, ContractError> {
+    only_minter_admin(deps.as_ref(), &info.sender)?;
+
+    let minter = deps.api.addr_validate(&address)?;
+    MINTERS.update(deps.storage, &minter, |minter_quota| -> StdResult<_> {
+        let mut minter_quota = minter_quota.ok_or_else(|| StdError::not_found("MinterQuota"))?;
+        minter_quota.remaining = quota;
+        Ok(minter_quota)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_quota")
+        .add_attribute("address", address)
+        .add_attribute("quota", quota))
+}
+
+/// Claw back the unvested balance of a revocable vesting to the vesting admin
+pub fn execute_revoke_vesting(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let admin = VESTING_ADMIN.may_load(deps.storage)?;
+    if admin.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let beneficiary = deps.api.addr_validate(&address)?;
+    let mut schedule = VESTING.load(deps.storage, &beneficiary)?;
+    if !schedule.revocable || schedule.revoked_at.is_some() {
+        return Err(ContractError::NotRevocable { address });
+    }
+    let clawback = locked(&schedule, env.block.time.seconds());
+    schedule.revoked_at = Some(env.block.time.seconds());
+    VESTING.save(deps.storage, &beneficiary, &schedule)?;
+    let index = REBASE_INDEX.may_load(deps.storage)?;
+
+    // The unvested tokens leave the allocation of the beneficiary, what vested stays claimable
+    if !clawback.is_zero() {
+        assert_compliant(
+            deps.as_ref(),
+            &info.sender,
+            beneficiary.as_str(),
+            info.sender.as_str(),
+            clawback,
+        )?;
+        release_vesting(deps, &env, &info.sender, clawback)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_vesting")
+        .add_attribute("address", address)
+        .add_attribute("amount", to_amount(clawback, index)))
+}
+
+/// Index the next page of balances held before the holder index existed, accounts touched
+/// since the migration are already indexed and skipped
+pub fn execute_index_holders(
+    mut deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let cursor = HOLDER_BACKFILL
+        .may_load(deps.storage)?
+        .ok_or(ContractError::HoldersIndexed {})?;
+
+    let limit = limit
+        .unwrap_or(DEFAULT_INDEX_LIMIT)
+        .clamp(1, MAX_INDEX_LIMIT) as usize;
+    let start = cursor.as_ref().map(Bound::exclusive);
+    let accounts = BALANCES
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    for account in &accounts {
+        seed_snapshots(deps.branch(), &[account.as_str()])?;
+    }
+
+    let complete = accounts.len() < limit;
+    if complete {
+        HOLDER_BACKFILL.remove(deps.storage);
+    } else {
+        HOLDER_BACKFILL.save(deps.storage, &accounts.last().cloned())?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "index_holders")
+        .add_attribute("indexed", accounts.len().to_string())
+        .add_attribute("complete", complete.to_string()))
+}
+
+/// Release the vested tokens the contract holds for the sender
+pub fn execute_claim_vesting(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut schedule = VESTING.load(deps.storage, &info.sender)?;
+    let claimable = vested(&schedule, env.block.time.seconds()) - schedule.claimed;
+    if claimable.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    assert_compliant(
+        deps.as_ref(),
+        &info.sender,
+        env.contract.address.as_str(),
+        info.sender.as_str(),
+        claimable,
+    )?;
+    schedule.claimed += claimable;
+    VESTING.save(deps.storage, &info.sender, &schedule)?;
+    let index = REBASE_INDEX.may_load(deps.storage)?;
+
+    release_vesting(deps, &env, &info.sender, claimable)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_vesting")
+        .add_attribute("address", info.sender)
+        .add_attribute("amount", to_amount(claimable, index)))
+}
+
+/// Moves the balance held by the contract for the vestings to `to`, schedules are in the
+/// same units as the balances so the contract always holds what they have left
+fn release_vesting(
+    mut deps: DepsMut,
+    env: &Env,
+    to: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let contract = &env.contract.address;
+    seed_snapshots(deps.branch(), &[contract.as_str()])?;
+    BALANCES.update(deps.storage, contract, |balance| -> StdResult<_> {
+        Ok(balance.unwrap_or_default().checked_sub(amount)?)
+    })?;
+    BALANCES.update(deps.storage, to, |balance| -> StdResult<_> {
+        Ok(balance.unwrap_or_default() + amount)
+    })?;
+
+    sync_balances(deps, env, &[contract.as_str(), to.as_str()])
+}
+
+/// Halt or resume every transfer, send, mint and burn
+pub fn execute_set_paused(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    only_pauser(deps.as_ref(), &info.sender)?;
+
+    PAUSE.update(deps.storage, |mut pause| -> StdResult<_> {
+        pause.paused = paused;
+        Ok(pause)
+    })?;
+
+    let action = if paused { "pause" } else { "unpause" };
+    Ok(Response::new().add_attribute("action", action))
+}
+
+/// Freeze or unfreeze a single account
+pub fn execute_set_frozen(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    frozen: bool,
+) -> Result<Response, ContractError> {
+    only_pauser(deps.as_ref(), &info.sender)?;
+
+    let addr = deps.api.addr_validate(&address)?;
+    if frozen {
+        FROZEN.save(deps.storage, &addr, &Empty {})?;
+    } else {
+        FROZEN.remove(deps.storage, &addr);
+    }
+
+    let action = if frozen {
+        "freeze_account"
+    } else {
+        "unfreeze_account"
+    };
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("address", address))
+}
+
+/// Block an address from sending and receiving tokens
+pub fn execute_add_to_deny_list(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    only_compliance_admin(deps.as_ref(), &info.sender)?;
+
+    let denied = deps.api.addr_validate(&address)?;
+    DENY_LIST.save(deps.storage, &denied, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_to_deny_list")
+        .add_attribute("address", address))
+}
+
+/// Unblock an address
+pub fn execute_remove_from_deny_list(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    only_compliance_admin(deps.as_ref(), &info.sender)?;
+
+    let denied = deps.api.addr_validate(&address)?;
+    DENY_LIST.remove(deps.storage, &denied);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_from_deny_list")
+        .add_attribute("address", address))
+}
+
+/// Set or unset the contract asked to approve every transfer
+pub fn execute_update_compliance_contract(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract: Option<String>,
+) -> Result<Response, ContractError> {
+    only_compliance_admin(deps.as_ref(), &info.sender)?;
+
+    let mut compliance = COMPLIANCE.load(deps.storage)?;
+    compliance.contract = contract
+        .as_deref()
+        .map(|contract| deps.api.addr_validate(contract))
+        .transpose()?;
+    COMPLIANCE.save(deps.storage, &compliance)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_compliance_contract")
+        .add_attribute("contract", contract.unwrap_or_else(|| "none".to_string())))
+}
+
+/// Hand the compliance admin role over or renounce it
+pub fn execute_update_compliance_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    admin: Option<String>,
+) -> Result<Response, ContractError> {
+    only_compliance_admin(deps.as_ref(), &info.sender)?;
+
+    let mut compliance = COMPLIANCE.load(deps.storage)?;
+    compliance.admin = admin
+        .as_deref()
+        .map(|admin| deps.api.addr_validate(admin))
+        .transpose()?;
+    COMPLIANCE.save(deps.storage, &compliance)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_compliance_admin")
+        .add_attribute("admin", admin.unwrap_or_else(|| "none".to_string())))
+}
+
+/// Enable or disable the receiver allowlist
+pub fn execute_set_receiver_allowlist(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    only_compliance_admin(deps.as_ref(), &info.sender)?;
+
+    let mut compliance = COMPLIANCE.load(deps.storage)?;
+    compliance.receiver_allowlist = enabled;
+    COMPLIANCE.save(deps.storage, &compliance)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_receiver_allowlist")
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+/// Let a contract be sent tokens while the receiver allowlist is enabled
+pub fn execute_add_allowed_receiver(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    only_compliance_admin(deps.as_ref(), &info.sender)?;
+
+    let receiver = deps.api.addr_validate(&address)?;
+    ALLOWED_RECEIVERS.save(deps.storage, &receiver, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_allowed_receiver")
+        .add_attribute("address", address))
+}
+
+/// Remove a contract from the receiver allowlist
+pub fn execute_remove_allowed_receiver(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    only_compliance_admin(deps.as_ref(), &info.sender)?;
+
+    let receiver = deps.api.addr_validate(&address)?;
+    ALLOWED_RECEIVERS.remove(deps.storage, &receiver);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_allowed_receiver")
+        .add_attribute("address", address))
+}
+
+/// Set an allowance approved off-chain by the owner
+#[allow(clippy::too_many_arguments)]
+pub fn execute_permit(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    owner: String,
+    spender: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+    nonce: u64,
+    signature: Binary,
+    pubkey: Binary,
+) -> Result<Response, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if owner_addr == spender_addr {
+        return Err(cw20_base::ContractError::CannotSetOwnAccount {}.into());
+    }
+    if expires.is_some_and(|expires| expires.is_expired(&env.block)) {
+        return Err(cw20_base::ContractError::Expired {}.into());
+    }
+
+    // The public key must be the one of the owner
+    if pubkey_to_address(&pubkey, &owner)? != owner {
+        return Err(ContractError::PubkeyMismatch { owner });
+    }
+
+    // Each permit can only be used once
+    let expected = PERMIT_NONCES
+        .may_load(deps.storage, &owner_addr)?
+        .unwrap_or_default();
+    if nonce != expected {
+        return Err(ContractError::InvalidNonce {
+            expected,
+            got: nonce,
+        });
+    }
+
+    // The signature covers the chain and the contract to prevent replays elsewhere
+    let payload = PermitPayload {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        owner: owner.clone(),
+        spender: spender.clone(),
+        amount,
+        expires,
+        nonce,
+    };
+    let hash = Sha256::digest(to_vec(&payload)?);
+    if !deps
+        .api
+        .secp256k1_verify(&hash, &signature, &pubkey)
+        .map_err(|_| ContractError::InvalidSignature {})?
+    {
+        return Err(ContractError::InvalidSignature {});
+    }
+    PERMIT_NONCES.save(deps.storage, &owner_addr, &(nonce + 1))?;
+
+    let allowance = AllowanceResponse {
+        allowance: amount,
+        expires: expires.unwrap_or_default(),
+    };
+    ALLOWANCES.save(deps.storage, (&owner_addr, &spender_addr), &allowance)?;
+    ALLOWANCES_SPENDER.save(deps.storage, (&spender_addr, &owner_addr), &allowance)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "permit")
+        .add_attribute("owner", owner)
+        .add_attribute("spender", spender)
+        .add_attribute("amount", amount)
+        .add_attribute("nonce", nonce.to_string()))
 }
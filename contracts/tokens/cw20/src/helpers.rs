@@ -0,0 +1,430 @@
+use crate::error::ContractError;
+use crate::msg::{CheckTransferResponse, ComplianceQueryMsg};
+use crate::state::{
+    EmissionConfig, EmissionSchedule, TaxConfig, VestingSchedule, ALLOWED_RECEIVERS,
+    BALANCE_SNAPSHOTS, COMPLIANCE, DENY_LIST, EMISSION, FROZEN, HOLDERS, HOLDER_COUNT,
+    MINTER_ADMIN, MINT_TRACKER, PAUSE, REBASE_INDEX, SNAPSHOT_SEED_HEIGHT, TAX, TAX_EXEMPT,
+    TOTAL_SUPPLY_SNAPSHOTS,
+};
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::{
+    Addr, Decimal, Deps, DepsMut, Empty, Env, StdError, StdResult, Storage, Uint128,
+};
+use cw20_base::state::{BALANCES, TOKEN_INFO};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+/// Converts an amount of tokens into shares, rounding down
+pub fn to_shares(amount: Uint128, index: Option<Decimal>) -> Uint128 {
+    match index {
+        Some(index) => amount.multiply_ratio(Decimal::one().atomics(), index.atomics()),
+        None => amount,
+    }
+}
+
+/// Converts shares into an amount of tokens, rounding down
+pub fn to_amount(shares: Uint128, index: Option<Decimal>) -> Uint128 {
+    match index {
+        Some(index) => shares.multiply_ratio(index.atomics(), Decimal::one().atomics()),
+        None => shares,
+    }
+}
+
+/// Basis points in a whole
+pub const BPS_DENOMINATOR: u16 = 10_000;
+
+/// Checks to enforce only the minter
+pub fn only_minter(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    let minter = TOKEN_INFO.load(deps.storage)?.mint.map(|mint| mint.minter);
+    if minter.as_ref() != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+/// Checks to enforce only the minter admin
+pub fn only_minter_admin(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    let admin = MINTER_ADMIN.may_load(deps.storage)?;
+    if admin.as_ref() != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+/// Validates the tax settings, a collector is required as soon as a tax is charged
+pub fn validate_tax(
+    deps: Deps,
+    tax_bps: u16,
+    collector: Option<&str>,
+) -> Result<TaxConfig, ContractError> {
+    if tax_bps > BPS_DENOMINATOR {
+        return Err(ContractError::InvalidTax(format!(
+            "tax_bps cannot exceed {}",
+            BPS_DENOMINATOR
+        )));
+    }
+    let collector = collector
+        .map(|collector| deps.api.addr_validate(collector))
+        .transpose()?;
+    if tax_bps > 0 && collector.is_none() {
+        return Err(ContractError::InvalidTax(
+            "a tax collector is required".to_string(),
+        ));
+    }
+
+    Ok(TaxConfig { tax_bps, collector })
+}
+
+/// The tax charged on a transfer
+pub struct Tax {
+    pub collector: Addr,
+    pub amount: Uint128,
+}
+
+/// Computes the tax owed on a transfer, `None` when nothing is charged
+pub fn calculate_tax(
+    deps: Deps,
+    from: &str,
+    to: &str,
+    amount: Uint128,
+) -> Result<Option<Tax>, ContractError> {
+    let tax = TAX.load(deps.storage)?;
+    let collector = match tax.collector {
+        Some(collector) if tax.tax_bps > 0 => collector,
+        _ => return Ok(None),
+    };
+
+    // Transfers from, to or involving the collector are not taxed
+    for party in [from, to] {
+        let address = deps.api.addr_validate(party)?;
+        if address == collector || TAX_EXEMPT.has(deps.storage, &address) {
+            return Ok(None);
+        }
+    }
+
+    let amount = amount.multiply_ratio(tax.tax_bps, BPS_DENOMINATOR);
+    if amount.is_zero() {
+        return Ok(None);
+    }
+
+    Ok(Some(Tax { collector, amount }))
+}
+
+/// Checks to enforce only the pauser
+pub fn only_pauser(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    let pause = PAUSE.load(deps.storage)?;
+    if pause.pauser.as_ref() != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+/// Rejects the operation if the token is paused or one of the accounts is frozen
+pub fn assert_not_paused(deps: Deps, accounts: &[&str]) -> Result<(), ContractError> {
+    if PAUSE.load(deps.storage)?.paused {
+        return Err(ContractError::Paused {});
+    }
+    for account in accounts {
+        let address = deps.api.addr_validate(account)?;
+        if FROZEN.has(deps.storage, &address) {
+            return Err(ContractError::Frozen {
+                address: address.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks to enforce only the compliance admin
+pub fn only_compliance_admin(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    let compliance = COMPLIANCE.load(deps.storage)?;
+    if compliance.admin.as_ref() != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+/// Rejects sending to a contract that is not allowed while the receiver allowlist is enabled
+pub fn assert_allowed_receiver(deps: Deps, contract: &str) -> Result<(), ContractError> {
+    if !COMPLIANCE.load(deps.storage)?.receiver_allowlist {
+        return Ok(());
+    }
+
+    let contract = deps.api.addr_validate(contract)?;
+    if !ALLOWED_RECEIVERS.has(deps.storage, &contract) {
+        return Err(ContractError::ReceiverNotAllowed {
+            contract: contract.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Rejects the transfer if a party is denied or if the compliance contract refuses it.
+/// `amount` is in shares in rebasing mode, the compliance contract is asked in tokens
+pub fn assert_compliant(
+    deps: Deps,
+    sender: &Addr,
+    from: &str,
+    to: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    for party in [sender.as_str(), from, to] {
+        let address = deps.api.addr_validate(party)?;
+        if DENY_LIST.has(deps.storage, &address) {
+            return Err(ContractError::Denied {
+                address: address.to_string(),
+            });
+        }
+    }
+
+    if let Some(contract) = COMPLIANCE.load(deps.storage)?.contract {
+        let index = REBASE_INDEX.may_load(deps.storage)?;
+        let res: CheckTransferResponse = deps.querier.query_wasm_smart(
+            contract,
+            &ComplianceQueryMsg::CheckTransfer {
+                from: from.to_string(),
+                to: to.to_string(),
+                amount: to_amount(amount, index),
+            },
+        )?;
+        if !res.allowed {
+            return Err(ContractError::ComplianceRejected {
+                reason: res.reason.unwrap_or_default(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Derives the address of a secp256k1 public key with the prefix of `like`
+pub fn pubkey_to_address(pubkey: &[u8], like: &str) -> StdResult<String> {
+    let (prefix, _, _) = bech32::decode(like).map_err(|e| StdError::generic_err(e.to_string()))?;
+    let hash = Ripemd160::digest(Sha256::digest(pubkey));
+    bech32::encode(&prefix, hash.to_base32(), Variant::Bech32)
+        .map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+/// Checkpoints the balance `accounts` held before the snapshots existed at the migration
+/// height and adds them to the holder index, must run before their balance changes
+pub fn seed_snapshots(deps: DepsMut, accounts: &[&str]) -> StdResult<()> {
+    let height = match SNAPSHOT_SEED_HEIGHT.may_load(deps.storage)? {
+        Some(height) => height,
+        None => return Ok(()),
+    };
+    for account in accounts {
+        let address = deps.api.addr_validate(account)?;
+        if BALANCE_SNAPSHOTS
+            .may_load(deps.storage, &address)?
+            .is_some()
+        {
+            continue;
+        }
+        let balance = BALANCES
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        if !balance.is_zero() {
+            BALANCE_SNAPSHOTS.save(deps.storage, &address, &balance, height)?;
+            index_holder(deps.storage, &address, Uint128::zero(), balance)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Records the balances of `accounts` and the total supply at the current height and moves
+/// the accounts in the holder index
+pub fn sync_balances(deps: DepsMut, env: &Env, accounts: &[&str]) -> Result<(), ContractError> {
+    let height = env.block.height;
+    for account in accounts {
+        let address = deps.api.addr_validate(account)?;
+        let balance = BALANCES
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        // The latest snapshot is the balance the holder index knows
+        let previous = BALANCE_SNAPSHOTS
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        index_holder(deps.storage, &address, previous, balance)?;
+        BALANCE_SNAPSHOTS.save(deps.storage, &address, &balance, height)?;
+    }
+
+    let total_supply = TOKEN_INFO.load(deps.storage)?.total_supply;
+    TOTAL_SUPPLY_SNAPSHOTS.save(deps.storage, &total_supply, height)?;
+
+    Ok(())
+}
+
+/// Validates the times of a vesting schedule
+pub fn validate_vesting(schedule: &VestingSchedule) -> Result<(), ContractError> {
+    if schedule.end <= schedule.start {
+        return Err(ContractError::InvalidVesting(
+            "end must be after start".to_string(),
+        ));
+    }
+    if schedule.cliff < schedule.start || schedule.cliff > schedule.end {
+        return Err(ContractError::InvalidVesting(
+            "cliff must be between start and end".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Amount vested at `time`, vesting stops when the schedule is revoked
+pub fn vested(schedule: &VestingSchedule, time: u64) -> Uint128 {
+    let time = schedule
+        .revoked_at
+        .map_or(time, |revoked| time.min(revoked));
+    if time < schedule.cliff {
+        Uint128::zero()
+    } else if time >= schedule.end {
+        schedule.amount
+    } else {
+        schedule
+            .amount
+            .multiply_ratio(time - schedule.start, schedule.end - schedule.start)
+    }
+}
+
+/// Amount that has not vested yet at `time`, nothing stays locked once revoked
+pub fn locked(schedule: &VestingSchedule, time: u64) -> Uint128 {
+    if schedule.revoked_at.is_some() {
+        return Uint128::zero();
+    }
+    schedule.amount - vested(schedule, time)
+}
+
+/// Validates the emission schedule and the epoch limit
+pub fn validate_emission(emission: &EmissionConfig) -> Result<(), ContractError> {
+    match &emission.schedule {
+        Some(EmissionSchedule::Linear { start, end, .. }) if end <= start => {
+            return Err(ContractError::InvalidEmission(
+                "end must be after start".to_string(),
+            ));
+        }
+        Some(EmissionSchedule::Stepwise { steps }) => {
+            if steps.is_empty() {
+                return Err(ContractError::InvalidEmission(
+                    "at least one step is required".to_string(),
+                ));
+            }
+            if steps.windows(2).any(|pair| pair[1].time <= pair[0].time) {
+                return Err(ContractError::InvalidEmission(
+                    "steps must be sorted by time".to_string(),
+                ));
+            }
+        }
+        _ => {}
+    }
+    if matches!(&emission.epoch_limit, Some(limit) if limit.duration == 0) {
+        return Err(ContractError::InvalidEmission(
+            "epoch duration cannot be zero".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Cumulative amount unlocked by the schedule at `time`
+fn unlocked(schedule: &EmissionSchedule, time: u64) -> Uint128 {
+    match schedule {
+        EmissionSchedule::Linear { start, end, amount } => {
+            if time <= *start {
+                Uint128::zero()
+            } else if time >= *end {
+                *amount
+            } else {
+                amount.multiply_ratio(time - start, end - start)
+            }
+        }
+        EmissionSchedule::Stepwise { steps } => steps
+            .iter()
+            .take_while(|step| step.time <= time)
+            .map(|step| step.amount)
+            .sum(),
+    }
+}
+
+/// Amount the minter can mint at the current block, `None` when unlimited
+pub fn mintable_now(storage: &dyn Storage, env: &Env) -> StdResult<Option<Uint128>> {
+    let emission = EMISSION.load(storage)?;
+    let tracker = MINT_TRACKER.load(storage)?;
+    let time = env.block.time.seconds();
+
+    let by_schedule = emission
+        .schedule
+        .map(|schedule| unlocked(&schedule, time).saturating_sub(tracker.minted));
+    let by_epoch = emission.epoch_limit.map(|limit| {
+        if time / limit.duration == tracker.epoch {
+            limit.amount.saturating_sub(tracker.epoch_minted)
+        } else {
+            limit.amount
+        }
+    });
+
+    Ok(match (by_schedule, by_epoch) {
+        (Some(schedule), Some(epoch)) => Some(schedule.min(epoch)),
+        (schedule, epoch) => schedule.or(epoch),
+    })
+}
+
+/// Records a mint against the emission limits, rejecting it if they are exceeded
+pub fn record_mint(
+    storage: &mut dyn Storage,
+    env: &Env,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(mintable) = mintable_now(storage, env)? {
+        if amount > mintable {
+            return Err(ContractError::MintLimitExceeded { mintable });
+        }
+    }
+
+    let emission = EMISSION.load(storage)?;
+    let mut tracker = MINT_TRACKER.load(storage)?;
+    tracker.minted += amount;
+    if let Some(limit) = emission.epoch_limit {
+        let epoch = env.block.time.seconds() / limit.duration;
+        if epoch != tracker.epoch {
+            tracker.epoch = epoch;
+            tracker.epoch_minted = Uint128::zero();
+        }
+        tracker.epoch_minted += amount;
+    }
+    MINT_TRACKER.save(storage, &tracker)?;
+
+    Ok(())
+}
+
+/// Moves an account in the holder index from its previous balance to its new one
+pub fn index_holder(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    previous: Uint128,
+    balance: Uint128,
+) -> StdResult<()> {
+    if previous == balance {
+        return Ok(());
+    }
+
+    let mut count = HOLDER_COUNT.may_load(storage)?.unwrap_or_default();
+    if previous.is_zero() {
+        count += 1;
+    } else {
+        HOLDERS.remove(storage, (previous.u128(), address));
+    }
+    if balance.is_zero() {
+        count -= 1;
+    } else {
+        HOLDERS.save(storage, (balance.u128(), address), &Empty {})?;
+    }
+
+    HOLDER_COUNT.save(storage, &count)
+}
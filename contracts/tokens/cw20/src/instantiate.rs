@@ -1,24 +1,146 @@
+use crate::error::ContractError;
+use crate::helpers::{sync_balances, validate_emission, validate_tax, validate_vesting};
+use crate::msg::InstantiateMsg;
+use crate::state::{
+    ComplianceConfig, EmissionConfig, MintTracker, PauseConfig, VestingSchedule, WrappedAsset,
+    ALLOWED_RECEIVERS, COMPLIANCE, EMISSION, HOLDER_COUNT, MINTER_ADMIN, MINT_TRACKER, PAUSE,
+    REBASE_INDEX, TAX, TAX_EXEMPT, VESTING, VESTING_ADMIN, WRAPPED,
+};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{Decimal, DepsMut, Empty, Env, MessageInfo, Response, Uint128};
 use cw2::set_contract_version;
-use cw20_base::ContractError;
+use cw20_spec::Cw20Coin;
 
-const CONTRACT_NAME: &str = "crates.io:halborn-cw20";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub(crate) const CONTRACT_NAME: &str = "crates.io:halborn-cw20";
+pub(crate) const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Name stored by the instances deployed before the wrapper set its own
+pub(crate) const LEGACY_CONTRACT_NAME: &str = "crates.io:cw20-base";
 
 // version info for migration info
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    msg: cw20_base::msg::InstantiateMsg,
+    mut msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let compliance = ComplianceConfig {
+        admin: msg
+            .compliance_admin
+            .as_deref()
+            .map(|admin| deps.api.addr_validate(admin))
+            .transpose()?,
+        contract: msg
+            .compliance_contract
+            .as_deref()
+            .map(|contract| deps.api.addr_validate(contract))
+            .transpose()?,
+        receiver_allowlist: msg.receiver_allowlist,
+    };
+    COMPLIANCE.save(deps.storage, &compliance)?;
+    for receiver in &msg.allowed_receivers {
+        let receiver = deps.api.addr_validate(receiver)?;
+        ALLOWED_RECEIVERS.save(deps.storage, &receiver, &Empty {})?;
+    }
+
+    let tax = validate_tax(deps.as_ref(), msg.tax_bps, msg.tax_collector.as_deref())?;
+    TAX.save(deps.storage, &tax)?;
+    for address in &msg.tax_exempt {
+        let exempt = deps.api.addr_validate(address)?;
+        TAX_EXEMPT.save(deps.storage, &exempt, &Empty {})?;
+    }
+
+    let emission = EmissionConfig {
+        schedule: msg.emission_schedule.clone(),
+        epoch_limit: msg.epoch_mint_limit.clone(),
+    };
+    validate_emission(&emission)?;
+    EMISSION.save(deps.storage, &emission)?;
+    MINT_TRACKER.save(deps.storage, &MintTracker::default())?;
+
+    let pause = PauseConfig {
+        pauser: msg
+            .pauser
+            .as_deref()
+            .map(|pauser| deps.api.addr_validate(pauser))
+            .transpose()?,
+        paused: false,
+    };
+    PAUSE.save(deps.storage, &pause)?;
+
+    if let Some(wrapped) = &msg.wrapped {
+        let wrapped = WrappedAsset {
+            denom: wrapped.denom.clone(),
+            vault: deps.api.addr_validate(&wrapped.vault)?,
+        };
+        WRAPPED.save(deps.storage, &wrapped)?;
+    }
+
+    if msg.rebasing {
+        REBASE_INDEX.save(deps.storage, &Decimal::one(), env.block.height)?;
+    }
+
+    if let Some(admin) = &msg.minter_admin {
+        MINTER_ADMIN.save(deps.storage, &deps.api.addr_validate(admin)?)?;
+    }
+
+    if let Some(admin) = &msg.vesting_admin {
+        VESTING_ADMIN.save(deps.storage, &deps.api.addr_validate(admin)?)?;
+    }
+    // Vesting tokens are minted to the contract, which releases them as they vest. The index
+    // starts at one, so the schedules are in shares from the start
+    let contract = env.contract.address.to_string();
+    for vesting in &msg.vesting_balances {
+        let beneficiary = deps.api.addr_validate(&vesting.address)?;
+        if VESTING.has(deps.storage, &beneficiary) {
+            return Err(ContractError::InvalidVesting(format!(
+                "duplicate vesting for {}",
+                beneficiary
+            )));
+        }
+        let schedule = VestingSchedule {
+            amount: vesting.amount,
+            cliff: vesting.cliff,
+            start: vesting.start,
+            end: vesting.end,
+            revocable: vesting.revocable,
+            revoked_at: None,
+            claimed: Uint128::zero(),
+        };
+        validate_vesting(&schedule)?;
+        VESTING.save(deps.storage, &beneficiary, &schedule)?;
+
+        match msg
+            .initial_balances
+            .iter_mut()
+            .find(|coin| coin.address == contract)
+        {
+            Some(coin) => coin.amount += vesting.amount,
+            None => msg.initial_balances.push(Cw20Coin {
+                address: contract.clone(),
+                amount: vesting.amount,
+            }),
+        }
+    }
+
+    let initial_accounts: Vec<String> = msg
+        .initial_balances
+        .iter()
+        .map(|coin| coin.address.clone())
+        .collect();
 
     /* Execute the instantiate method from cw_20_base as the code from that
     library is already battle tested we do not have to re-write the full
     functionality: https://github.com/CosmWasm/cw-plus/tree/main/contracts/cw20-base*/
-    Ok(cw20_base::contract::instantiate(deps, env, info, msg)?)
+    let res = cw20_base::contract::instantiate(deps.branch(), env.clone(), info, msg.into())?;
+
+    HOLDER_COUNT.save(deps.storage, &0)?;
+    let accounts: Vec<&str> = initial_accounts.iter().map(String::as_str).collect();
+    sync_balances(deps.branch(), &env, &accounts)?;
+
+    // cw20_base sets its own version, overwrite it with ours
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(res)
 }
@@ -1,3 +1,14 @@
+mod error;
 pub mod execute;
+mod helpers;
 pub mod instantiate;
+pub mod migrate;
+pub mod msg;
+
+#[cfg(test)]
+mod multitest;
+
 pub mod query;
+pub mod state;
+
+pub use crate::error::ContractError;
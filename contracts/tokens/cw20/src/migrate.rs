@@ -0,0 +1,94 @@
+use crate::error::ContractError;
+use crate::instantiate::{CONTRACT_NAME, CONTRACT_VERSION, LEGACY_CONTRACT_NAME};
+use crate::msg::MigrateMsg;
+use crate::state::{
+    ComplianceConfig, EmissionConfig, MintTracker, PauseConfig, TaxConfig, COMPLIANCE, EMISSION,
+    HOLDER_BACKFILL, HOLDER_COUNT, MINT_TRACKER, PAUSE, SNAPSHOT_SEED_HEIGHT, TAX,
+    TOTAL_SUPPLY_SNAPSHOTS,
+};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{DepsMut, Env, Response, Storage};
+use cw2::{get_contract_version, set_contract_version};
+use cw20_base::state::TOKEN_INFO;
+use semver::Version;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME && stored.contract != LEGACY_CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: stored.contract,
+        });
+    }
+
+    // Legacy instances store the cw20-base version and hold none of the extensions
+    let previous_version: Version = if stored.contract == LEGACY_CONTRACT_NAME {
+        Version::new(0, 0, 0)
+    } else {
+        stored.version.parse()?
+    };
+
+    // Downgrades could leave state the older code does not understand
+    let new_version: Version = CONTRACT_VERSION.parse()?;
+    if previous_version > new_version {
+        return Err(ContractError::CannotMigrateVersion {
+            previous_version: stored.version,
+            new_version: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    upgrade_state(deps.storage, &env, &previous_version)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous_version.to_string())
+        .add_attribute("new_version", CONTRACT_VERSION))
+}
+
+/// Initializes the state of the extensions introduced after `previous_version`
+fn upgrade_state(
+    storage: &mut dyn Storage,
+    env: &Env,
+    _previous_version: &Version,
+) -> Result<(), ContractError> {
+    // Compliance is disabled on instances created without it
+    if COMPLIANCE.may_load(storage)?.is_none() {
+        COMPLIANCE.save(storage, &ComplianceConfig::default())?;
+    }
+
+    // Transfers stay untaxed on instances created without a tax
+    if TAX.may_load(storage)?.is_none() {
+        TAX.save(storage, &TaxConfig::default())?;
+    }
+
+    // Mints stay limited by the cap only on instances created without a schedule
+    if EMISSION.may_load(storage)?.is_none() {
+        EMISSION.save(storage, &EmissionConfig::default())?;
+        MINT_TRACKER.save(storage, &MintTracker::default())?;
+    }
+
+    // Instances created without a pauser cannot be paused
+    if PAUSE.may_load(storage)?.is_none() {
+        PAUSE.save(storage, &PauseConfig::default())?;
+    }
+
+    // Balances held before the snapshots existed are checkpointed at the migration height
+    // when each account is first touched, walking every balance here could run out of gas
+    if TOTAL_SUPPLY_SNAPSHOTS.may_load(storage)?.is_none() {
+        let height = env.block.height;
+        SNAPSHOT_SEED_HEIGHT.save(storage, &height)?;
+        let total_supply = TOKEN_INFO.load(storage)?.total_supply;
+        TOTAL_SUPPLY_SNAPSHOTS.save(storage, &total_supply, height)?;
+    }
+
+    // Holders from before the index existed are ranked along with their snapshot seed, or
+    // by `IndexHolders` for accounts that never move
+    if HOLDER_COUNT.may_load(storage)?.is_none() {
+        HOLDER_COUNT.save(storage, &0)?;
+        HOLDER_BACKFILL.save(storage, &None)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,498 @@
+use crate::state::{EmissionSchedule, EpochLimit, VestingSchedule};
+use cosmwasm_std::{Binary, Decimal, Uint128};
+use cw20_base::msg::InstantiateMarketingInfo;
+use cw20_spec::{Cw20Coin, Expiration, Logo, MinterResponse};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub initial_balances: Vec<Cw20Coin>,
+    pub mint: Option<MinterResponse>,
+    pub marketing: Option<InstantiateMarketingInfo>,
+    /// Address managing the deny-list
+    pub compliance_admin: Option<String>,
+    /// Contract asked to approve every transfer
+    pub compliance_contract: Option<String>,
+    /// Only let `allowed_receivers` be sent tokens with a hook
+    #[serde(default)]
+    pub receiver_allowlist: bool,
+    /// Contracts that can be sent tokens while the receiver allowlist is enabled
+    #[serde(default)]
+    pub allowed_receivers: Vec<String>,
+    /// Share of every transfer credited to the tax collector, in basis points
+    #[serde(default)]
+    pub tax_bps: u16,
+    /// Address receiving the transfer tax, required when `tax_bps` is set
+    pub tax_collector: Option<String>,
+    /// Addresses whose transfers are not taxed
+    #[serde(default)]
+    pub tax_exempt: Vec<String>,
+    /// Limits the cumulative amount minted by block time
+    pub emission_schedule: Option<EmissionSchedule>,
+    /// Limits the amount minted within each epoch
+    pub epoch_mint_limit: Option<EpochLimit>,
+    /// Address that can pause the token and freeze accounts
+    pub pauser: Option<String>,
+    /// The native asset the token is a receipt for
+    pub wrapped: Option<WrappedAssetInfo>,
+    /// Balances held by the token contract and released to each beneficiary as they vest
+    #[serde(default)]
+    pub vesting_balances: Vec<VestingBalance>,
+    /// Address that can revoke vestings
+    pub vesting_admin: Option<String>,
+    /// Address that manages the minters limited by a quota
+    pub minter_admin: Option<String>,
+    /// Store balances as shares of a supply the minter can rebase. Amounts, allowances, quotas
+    /// and mint limits stay in tokens, vestings follow the rebases like any balance
+    #[serde(default)]
+    pub rebasing: bool,
+}
+
+/// Times are in seconds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingBalance {
+    pub address: String,
+    pub amount: Uint128,
+    pub cliff: u64,
+    pub start: u64,
+    pub end: u64,
+    #[serde(default)]
+    pub revocable: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WrappedAssetInfo {
+    pub denom: String,
+    pub vault: String,
+}
+
+impl From<InstantiateMsg> for cw20_base::msg::InstantiateMsg {
+    fn from(msg: InstantiateMsg) -> cw20_base::msg::InstantiateMsg {
+        cw20_base::msg::InstantiateMsg {
+            name: msg.name,
+            symbol: msg.symbol,
+            decimals: msg.decimals,
+            initial_balances: msg.initial_balances,
+            mint: msg.mint,
+            marketing: msg.marketing,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+    Burn {
+        amount: Uint128,
+    },
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    SendFrom {
+        owner: String,
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    BurnFrom {
+        owner: String,
+        amount: Uint128,
+    },
+    Mint {
+        recipient: String,
+        amount: Uint128,
+    },
+    UpdateMinter {
+        new_minter: Option<String>,
+    },
+    UpdateMarketing {
+        project: Option<String>,
+        description: Option<String>,
+        marketing: Option<String>,
+    },
+    UploadLogo(Logo),
+    /// Transfer to several recipients atomically
+    BatchTransfer {
+        transfers: Vec<TransferLeg>,
+    },
+    /// Send to several contracts atomically
+    BatchSend {
+        sends: Vec<SendLeg>,
+    },
+    /// Block an address from sending and receiving, compliance admin only
+    AddToDenyList {
+        address: String,
+    },
+    /// Unblock an address, compliance admin only
+    RemoveFromDenyList {
+        address: String,
+    },
+    /// Set or unset the compliance contract, compliance admin only
+    UpdateComplianceContract {
+        contract: Option<String>,
+    },
+    /// Hand the compliance admin role over or renounce it, compliance admin only
+    UpdateComplianceAdmin {
+        admin: Option<String>,
+    },
+    /// Enable or disable the receiver allowlist, compliance admin only
+    SetReceiverAllowlist {
+        enabled: bool,
+    },
+    /// Let a contract be sent tokens while the allowlist is enabled, compliance admin only
+    AddAllowedReceiver {
+        address: String,
+    },
+    /// Remove a contract from the receiver allowlist, compliance admin only
+    RemoveAllowedReceiver {
+        address: String,
+    },
+    /// Update the transfer tax, minter only
+    UpdateTax {
+        tax_bps: u16,
+        tax_collector: Option<String>,
+    },
+    /// Exempt an address from the transfer tax or remove its exemption, minter only
+    SetTaxExemption {
+        address: String,
+        exempt: bool,
+    },
+    /// Set the amount of tokens per share, changing every balance at once, minter only
+    Rebase {
+        new_index: Decimal,
+    },
+    /// Allow an address to mint up to `quota`, minter admin only
+    AddMinter {
+        address: String,
+        quota: Uint128,
+    },
+    /// Remove a minter, minter admin only
+    RemoveMinter {
+        address: String,
+    },
+    /// Reset the amount a minter can still mint, minter admin only
+    SetQuota {
+        address: String,
+        quota: Uint128,
+    },
+    /// Claw back the unvested balance of a revocable vesting, vesting admin only
+    RevokeVesting {
+        address: String,
+    },
+    /// Release the vested tokens held for the sender
+    ClaimVesting {},
+    /// Add the next `limit` balances held before the holder index existed to it, anyone can
+    /// call it until every holder is indexed
+    IndexHolders {
+        limit: Option<u32>,
+    },
+    /// Halt every transfer, send, mint and burn, pauser only
+    Pause {},
+    /// Resume the token, pauser only
+    Unpause {},
+    /// Stop an account from moving or receiving tokens, pauser only
+    FreezeAccount {
+        address: String,
+    },
+    /// Release a frozen account, pauser only
+    UnfreezeAccount {
+        address: String,
+    },
+    /// Set an allowance approved off-chain by the owner, the signature covers the
+    /// JSON serialized `PermitPayload`
+    Permit {
+        owner: String,
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+        nonce: u64,
+        signature: Binary,
+        pubkey: Binary,
+    },
+}
+
+/// One recipient of a batch transfer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferLeg {
+    pub recipient: String,
+    pub amount: Uint128,
+}
+
+/// One receiving contract of a batch send
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SendLeg {
+    pub contract: String,
+    pub amount: Uint128,
+    pub msg: Binary,
+}
+
+/// The payload signed by the owner of a permit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitPayload {
+    pub chain_id: String,
+    pub contract: String,
+    pub owner: String,
+    pub spender: String,
+    pub amount: Uint128,
+    pub expires: Option<Expiration>,
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Balance {
+        address: String,
+    },
+    TokenInfo {},
+    Minter {},
+    Allowance {
+        owner: String,
+        spender: String,
+    },
+    AllAllowances {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    AllSpenderAllowances {
+        spender: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    AllAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    MarketingInfo {},
+    DownloadLogo {},
+    /// Returns the compliance admin and contract
+    ComplianceInfo {},
+    /// Returns the blocked addresses
+    DenyList {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns whether the receiver allowlist is enabled and the allowed receivers
+    AllowedReceivers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the nonce the next permit of the owner must use
+    PermitNonce {
+        owner: String,
+    },
+    /// Returns the balance of the address at the beginning of the block at `height`
+    BalanceAt {
+        address: String,
+        height: u64,
+    },
+    /// Returns the total supply at the beginning of the block at `height`
+    TotalSupplyAt {
+        height: u64,
+    },
+    /// Returns the amount the minter can mint at the current block
+    MintableNow {},
+    /// Returns the global pause state and the frozen accounts
+    PauseInfo {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the holders ranked by decreasing balance
+    TopHolders {
+        limit: Option<u32>,
+        start_after: Option<String>,
+    },
+    /// Returns the number of accounts holding a non-zero balance
+    HolderCount {},
+    /// Returns the native asset the token is a receipt for
+    WrappedAsset {},
+    /// Compares the bank balance of the vault with the total supply
+    Backing {},
+    /// Returns the vesting schedule and progress of the address
+    VestingInfo {
+        address: String,
+    },
+    /// Returns the minters limited by a quota
+    Minters {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the amount of tokens per share, `None` outside of rebasing mode
+    RebaseIndex {},
+    /// Returns the transfer tax settings
+    TaxInfo {},
+    /// Returns the addresses exempted from the transfer tax
+    TaxExemptions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+/// Query the compliance contract must answer for every transfer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ComplianceQueryMsg {
+    CheckTransfer {
+        from: String,
+        to: String,
+        amount: Uint128,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckTransferResponse {
+    pub allowed: bool,
+    pub reason: Option<String>,
+}
+
+/// Query the vault of a wrapped asset must answer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultQueryMsg {
+    VaultInfo { denom: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VaultInfoResponse {
+    pub native_decimals: u8,
+    pub receipt_decimals: u8,
+    /// Native amount the vault keeps that backs no receipt
+    pub dust: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ComplianceInfoResponse {
+    pub admin: Option<String>,
+    pub contract: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenyListResponse {
+    pub addresses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowedReceiversResponse {
+    pub enabled: bool,
+    pub receivers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitNonceResponse {
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalSupplyResponse {
+    pub total_supply: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintableNowResponse {
+    /// `None` when neither an emission limit nor a cap applies
+    pub mintable: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseInfoResponse {
+    pub pauser: Option<String>,
+    pub paused: bool,
+    pub frozen: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TopHoldersResponse {
+    pub holders: Vec<Cw20Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HolderCountResponse {
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WrappedAssetResponse {
+    pub wrapped: Option<WrappedAssetInfo>,
+}
+
+/// `backing` is the vault balance net of its dust converted to receipt units, so it compares
+/// directly with the total supply
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BackingResponse {
+    pub denom: String,
+    pub vault: String,
+    /// Native balance of the vault, in native units
+    pub vault_balance: Uint128,
+    /// Vault balance net of its dust, in receipt units
+    pub backing: Uint128,
+    pub total_supply: Uint128,
+    pub fully_backed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingInfoResponse {
+    /// In shares in rebasing mode, the amounts below are in tokens
+    pub schedule: Option<VestingSchedule>,
+    pub vested: Uint128,
+    pub locked: Uint128,
+    /// Vested amount not claimed yet
+    pub claimable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MinterInfo {
+    pub address: String,
+    pub remaining: Uint128,
+    pub minted: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintersResponse {
+    pub admin: Option<String>,
+    pub minters: Vec<MinterInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RebaseIndexResponse {
+    pub index: Option<Decimal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TaxInfoResponse {
+    pub tax_bps: u16,
+    pub tax_collector: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TaxExemptionsResponse {
+    pub addresses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
@@ -0,0 +1,2260 @@
+#![cfg(test)]
+use crate::error::ContractError;
+use crate::execute::MAX_BATCH_SIZE;
+use crate::instantiate::CONTRACT_NAME;
+use crate::msg::{
+    AllowedReceiversResponse, BackingResponse, CheckTransferResponse, ComplianceInfoResponse,
+    ComplianceQueryMsg, DenyListResponse, ExecuteMsg, HolderCountResponse, InstantiateMsg,
+    MigrateMsg, MintableNowResponse, MinterInfo, MintersResponse, PauseInfoResponse,
+    PermitNonceResponse, PermitPayload, QueryMsg, SendLeg, TaxInfoResponse, TopHoldersResponse,
+    TotalSupplyResponse, TransferLeg, VaultInfoResponse, VaultQueryMsg, VestingBalance,
+    VestingInfoResponse, WrappedAssetInfo, WrappedAssetResponse,
+};
+use crate::state::{EmissionSchedule, EmissionStep, EpochLimit};
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::testing::{mock_dependencies, mock_env};
+use cosmwasm_std::{
+    coins, to_binary, to_vec, Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, Event, MessageInfo,
+    Response, StdResult, Uint128,
+};
+use cw20_spec::{
+    AllowanceResponse, BalanceResponse, Cw20Coin, Cw20ReceiveMsg, MinterResponse, TokenInfoResponse,
+};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use k256::ecdsa::signature::Signer;
+use k256::ecdsa::{Signature, SigningKey};
+use ripemd::Ripemd160;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const DEPLOYER: &str = "deployer";
+const MINTER: &str = "minter";
+const ROB: &str = "rob";
+const STEVE: &str = "steve";
+const COMPLIANCE_ADMIN: &str = "compliance";
+const COLLECTOR: &str = "collector";
+const PAUSER: &str = "pauser";
+const VESTING_ADMIN: &str = "vesting_admin";
+const MINTER_ADMIN: &str = "minter_admin";
+const INITIAL_BALANCE: u128 = 1_000_000;
+
+pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::execute::execute,
+        crate::instantiate::instantiate,
+        crate::query::query,
+    )
+    .with_migrate(crate::migrate::migrate);
+    Box::new(contract)
+}
+
+pub fn contract_cw20_base() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+// Rejects every transfer above 1_000 tokens
+pub fn contract_compliance() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::default())
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::default())
+        },
+        |_deps: Deps, _env: Env, msg: ComplianceQueryMsg| -> StdResult<Binary> {
+            let ComplianceQueryMsg::CheckTransfer { amount, .. } = msg;
+            let allowed = amount <= Uint128::new(1_000);
+            to_binary(&CheckTransferResponse {
+                allowed,
+                reason: (!allowed).then(|| "limit".to_string()),
+            })
+        },
+    );
+    Box::new(contract)
+}
+
+// Holds native tokens with 8 decimals behind receipts with 6, keeping 50 of dust
+pub fn contract_vault() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::default())
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::default())
+        },
+        |_deps: Deps, _env: Env, msg: VaultQueryMsg| -> StdResult<Binary> {
+            let VaultQueryMsg::VaultInfo { .. } = msg;
+            to_binary(&VaultInfoResponse {
+                native_decimals: 8,
+                receipt_decimals: 6,
+                dust: Uint128::new(50),
+            })
+        },
+    );
+    Box::new(contract)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiverMsg {
+    Receive(Cw20ReceiveMsg),
+}
+
+// Reports the amount of every cw20 it is sent
+pub fn contract_receiver() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, msg: ReceiverMsg| -> StdResult<Response> {
+            let ReceiverMsg::Receive(msg) = msg;
+            Ok(Response::new().add_attribute("received", msg.amount))
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::default())
+        },
+        |_deps: Deps, _env: Env, _msg: Empty| -> StdResult<Binary> { to_binary(&Empty {}) },
+    );
+    Box::new(contract)
+}
+
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        name: "Wrapped".to_string(),
+        symbol: "WRAP".to_string(),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+            address: ROB.to_string(),
+            amount: Uint128::new(INITIAL_BALANCE),
+        }],
+        marketing: None,
+        mint: Some(MinterResponse {
+            minter: MINTER.to_string(),
+            cap: None,
+        }),
+        compliance_admin: Some(COMPLIANCE_ADMIN.to_string()),
+        compliance_contract: None,
+        receiver_allowlist: false,
+        allowed_receivers: vec![],
+        tax_bps: 0,
+        tax_collector: None,
+        tax_exempt: vec![],
+        emission_schedule: None,
+        epoch_mint_limit: None,
+        pauser: Some(PAUSER.to_string()),
+        wrapped: None,
+        vesting_balances: vec![],
+        vesting_admin: None,
+        minter_admin: Some(MINTER_ADMIN.to_string()),
+        rebasing: false,
+    }
+}
+
+// Instantiates the cw20 wrapper with the deployer as the migration admin
+fn setup_contract(router: &mut App) -> (u64, Addr) {
+    let code_id = router.store_code(contract_cw20());
+    let cw20 = router
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(DEPLOYER),
+            &instantiate_msg(),
+            &[],
+            "cw20",
+            Some(DEPLOYER.to_string()),
+        )
+        .unwrap();
+    (code_id, cw20)
+}
+
+fn query_balance(router: &App, cw20: &Addr, address: &str) -> Uint128 {
+    let res: BalanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            cw20,
+            &QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    res.balance
+}
+
+fn query_balance_at(router: &App, cw20: &Addr, address: &str, height: u64) -> Uint128 {
+    let res: BalanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            cw20,
+            &QueryMsg::BalanceAt {
+                address: address.to_string(),
+                height,
+            },
+        )
+        .unwrap();
+    res.balance
+}
+
+fn query_total_supply_at(router: &App, cw20: &Addr, height: u64) -> Uint128 {
+    let res: TotalSupplyResponse = router
+        .wrap()
+        .query_wasm_smart(cw20, &QueryMsg::TotalSupplyAt { height })
+        .unwrap();
+    res.total_supply
+}
+
+#[test]
+fn test_migrate_keeps_balances_and_allowances() {
+    let mut router = App::default();
+    let (code_id, cw20) = setup_contract(&mut router);
+
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: STEVE.to_string(),
+        amount: Uint128::new(300),
+        expires: None,
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    let res = router
+        .migrate_contract(
+            Addr::unchecked(DEPLOYER),
+            cw20.clone(),
+            &MigrateMsg {},
+            code_id,
+        )
+        .unwrap();
+    assert!(res.has_event(&Event::new("wasm").add_attribute("action", "migrate")));
+
+    assert_eq!(
+        query_balance(&router, &cw20, ROB),
+        Uint128::new(INITIAL_BALANCE)
+    );
+    let res: AllowanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &cw20,
+            &QueryMsg::Allowance {
+                owner: ROB.to_string(),
+                spender: STEVE.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.allowance, Uint128::new(300));
+}
+
+#[test]
+fn test_migrate_legacy_instance() {
+    let mut router = App::default();
+    let (code_id, _cw20) = setup_contract(&mut router);
+
+    // Instances deployed before the wrapper set its own version are stored as cw20-base
+    let base_code_id = router.store_code(contract_cw20_base());
+    let mut legacy_msg = cw20_base::msg::InstantiateMsg::from(instantiate_msg());
+    legacy_msg.initial_balances.push(Cw20Coin {
+        address: COLLECTOR.to_string(),
+        amount: Uint128::new(500),
+    });
+    let legacy = router
+        .instantiate_contract(
+            base_code_id,
+            Addr::unchecked(DEPLOYER),
+            &legacy_msg,
+            &[],
+            "legacy",
+            Some(DEPLOYER.to_string()),
+        )
+        .unwrap();
+
+    router
+        .migrate_contract(
+            Addr::unchecked(DEPLOYER),
+            legacy.clone(),
+            &MigrateMsg {},
+            code_id,
+        )
+        .unwrap();
+    assert_eq!(
+        query_balance(&router, &legacy, ROB),
+        Uint128::new(INITIAL_BALANCE)
+    );
+
+    let version = cw2::query_contract_info(&router, legacy.clone()).unwrap();
+    assert_eq!(version.contract, CONTRACT_NAME);
+
+    // Holders from before the migration are indexed once touched or by `IndexHolders`
+    let res: HolderCountResponse = router
+        .wrap()
+        .query_wasm_smart(&legacy, &QueryMsg::HolderCount {})
+        .unwrap();
+    assert_eq!(res.count, 0);
+
+    // Balances held before the migration are checkpointed at the migration height
+    let migration_height = router.block_info().height;
+    router.update_block(|block| block.height += 1);
+    let height = router.block_info().height;
+    assert_eq!(
+        query_balance_at(&router, &legacy, ROB, height),
+        Uint128::new(INITIAL_BALANCE)
+    );
+
+    // The checkpoint is kept once the account is touched
+    let msg = ExecuteMsg::Transfer {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(100),
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), legacy.clone(), &msg, &[])
+        .unwrap();
+    let res: HolderCountResponse = router
+        .wrap()
+        .query_wasm_smart(&legacy, &QueryMsg::HolderCount {})
+        .unwrap();
+    assert_eq!(res.count, 2);
+
+    // Anyone can index the holders that never moved, touched accounts are not counted twice
+    let index_holders = |router: &mut App, limit| {
+        router.execute_contract(
+            Addr::unchecked(STEVE),
+            legacy.clone(),
+            &ExecuteMsg::IndexHolders { limit },
+            &[],
+        )
+    };
+    index_holders(&mut router, Some(1)).unwrap();
+    assert_eq!(
+        query_top_holders(&router, &legacy, None, None),
+        vec![
+            (ROB.to_string(), INITIAL_BALANCE - 100),
+            (COLLECTOR.to_string(), 500),
+            (STEVE.to_string(), 100),
+        ]
+    );
+    index_holders(&mut router, None).unwrap();
+    let res: HolderCountResponse = router
+        .wrap()
+        .query_wasm_smart(&legacy, &QueryMsg::HolderCount {})
+        .unwrap();
+    assert_eq!(res.count, 3);
+    let err = index_holders(&mut router, None).unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::HoldersIndexed {}
+    ));
+    router.update_block(|block| block.height += 1);
+    assert_eq!(
+        query_balance_at(&router, &legacy, ROB, migration_height),
+        Uint128::zero()
+    );
+    assert_eq!(
+        query_balance_at(&router, &legacy, ROB, height),
+        Uint128::new(INITIAL_BALANCE)
+    );
+    assert_eq!(
+        query_balance_at(&router, &legacy, ROB, height + 1),
+        Uint128::new(INITIAL_BALANCE - 100)
+    );
+
+    // Compliance is disabled after the migration
+    let res: ComplianceInfoResponse = router
+        .wrap()
+        .query_wasm_smart(legacy, &QueryMsg::ComplianceInfo {})
+        .unwrap();
+    assert_eq!(
+        res,
+        ComplianceInfoResponse {
+            admin: None,
+            contract: None,
+        }
+    );
+}
+
+#[test]
+fn test_migrate_rejects_other_contract() {
+    let mut deps = mock_dependencies();
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:cw721-base", "0.16.0").unwrap();
+
+    let err = crate::migrate::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert!(matches!(err, ContractError::CannotMigrate { .. }));
+}
+
+#[test]
+fn test_migrate_rejects_downgrade() {
+    let mut deps = mock_dependencies();
+    cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+
+    let err = crate::migrate::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert!(matches!(err, ContractError::CannotMigrateVersion { .. }));
+}
+
+#[test]
+fn test_deny_list() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    // Only the compliance admin can block addresses
+    let msg = ExecuteMsg::AddToDenyList {
+        address: STEVE.to_string(),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    router
+        .execute_contract(Addr::unchecked(COMPLIANCE_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    let res: DenyListResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &cw20,
+            &QueryMsg::DenyList {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.addresses, vec![STEVE.to_string()]);
+
+    // Blocked addresses can neither receive nor be minted to
+    let msg = ExecuteMsg::Transfer {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(100),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Denied { .. }
+    ));
+    let mint = ExecuteMsg::Mint {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(100),
+    };
+    router
+        .execute_contract(Addr::unchecked(MINTER), cw20.clone(), &mint, &[])
+        .unwrap_err();
+
+    // Unblocked addresses receive again
+    let unblock = ExecuteMsg::RemoveFromDenyList {
+        address: STEVE.to_string(),
+    };
+    router
+        .execute_contract(
+            Addr::unchecked(COMPLIANCE_ADMIN),
+            cw20.clone(),
+            &unblock,
+            &[],
+        )
+        .unwrap();
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(100));
+}
+
+#[test]
+fn test_update_compliance_admin() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    let msg = ExecuteMsg::UpdateComplianceAdmin {
+        admin: Some(STEVE.to_string()),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    router
+        .execute_contract(Addr::unchecked(COMPLIANCE_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    // Only the new admin manages the deny-list
+    let block = ExecuteMsg::AddToDenyList {
+        address: ROB.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(COMPLIANCE_ADMIN), cw20.clone(), &block, &[])
+        .unwrap_err();
+    router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &block, &[])
+        .unwrap();
+
+    let res: ComplianceInfoResponse = router
+        .wrap()
+        .query_wasm_smart(&cw20, &QueryMsg::ComplianceInfo {})
+        .unwrap();
+    assert_eq!(res.admin, Some(STEVE.to_string()));
+}
+
+#[test]
+fn test_compliance_contract() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    let compliance_code_id = router.store_code(contract_compliance());
+    let compliance = router
+        .instantiate_contract(
+            compliance_code_id,
+            Addr::unchecked(DEPLOYER),
+            &Empty {},
+            &[],
+            "compliance",
+            None,
+        )
+        .unwrap();
+    let msg = ExecuteMsg::UpdateComplianceContract {
+        contract: Some(compliance.to_string()),
+    };
+    router
+        .execute_contract(Addr::unchecked(COMPLIANCE_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = ExecuteMsg::Transfer {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(2_000),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap().to_string(),
+        "Transfer rejected by the compliance contract: limit"
+    );
+
+    let msg = ExecuteMsg::Transfer {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(1_000),
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(1_000));
+}
+
+// Derives the account address controlled by a signing key
+fn key_address(key: &SigningKey) -> String {
+    let hash = Ripemd160::digest(Sha256::digest(key.verifying_key().to_bytes()));
+    bech32::encode("wasm", hash.to_base32(), Variant::Bech32).unwrap()
+}
+
+// Signs the permit payload the way a wallet would
+fn sign_permit(key: &SigningKey, payload: &PermitPayload) -> Binary {
+    let signature: Signature = key.sign(&to_vec(payload).unwrap());
+    Binary::from(signature.as_ref())
+}
+
+#[test]
+fn test_permit() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    // The owner is the address of the signing key
+    let key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+    let pubkey = Binary::from(key.verifying_key().to_bytes().as_slice());
+    let owner = key_address(&key);
+
+    let msg = ExecuteMsg::Transfer {
+        recipient: owner.clone(),
+        amount: Uint128::new(1_000),
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    let payload = PermitPayload {
+        chain_id: router.block_info().chain_id,
+        contract: cw20.to_string(),
+        owner: owner.clone(),
+        spender: STEVE.to_string(),
+        amount: Uint128::new(400),
+        expires: None,
+        nonce: 0,
+    };
+    let permit = ExecuteMsg::Permit {
+        owner: owner.clone(),
+        spender: STEVE.to_string(),
+        amount: Uint128::new(400),
+        expires: None,
+        nonce: 0,
+        signature: sign_permit(&key, &payload),
+        pubkey: pubkey.clone(),
+    };
+
+    // A tampered amount does not match the signature
+    let tampered = ExecuteMsg::Permit {
+        owner: owner.clone(),
+        spender: STEVE.to_string(),
+        amount: Uint128::new(1_000),
+        expires: None,
+        nonce: 0,
+        signature: sign_permit(&key, &payload),
+        pubkey,
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &tampered, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidSignature {}
+    ));
+
+    // Anyone can submit the permit, the spender can then pull the tokens
+    router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &permit, &[])
+        .unwrap();
+    let msg = ExecuteMsg::TransferFrom {
+        owner: owner.clone(),
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(400),
+    };
+    router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(400));
+
+    // The permit cannot be replayed
+    let err = router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &permit, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidNonce {
+            expected: 1,
+            got: 0
+        }
+    ));
+    let res: PermitNonceResponse = router
+        .wrap()
+        .query_wasm_smart(&cw20, &QueryMsg::PermitNonce { owner })
+        .unwrap();
+    assert_eq!(res.nonce, 1);
+}
+
+#[test]
+fn test_permit_rejects_foreign_pubkey() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    let key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+    let other = SigningKey::from_bytes(&[8u8; 32]).unwrap();
+    let owner = key_address(&key);
+
+    // A key signing for an address it does not control is rejected
+    let payload = PermitPayload {
+        chain_id: router.block_info().chain_id,
+        contract: cw20.to_string(),
+        owner: owner.clone(),
+        spender: STEVE.to_string(),
+        amount: Uint128::new(400),
+        expires: None,
+        nonce: 0,
+    };
+    let msg = ExecuteMsg::Permit {
+        owner,
+        spender: STEVE.to_string(),
+        amount: Uint128::new(400),
+        expires: None,
+        nonce: 0,
+        signature: sign_permit(&other, &payload),
+        pubkey: Binary::from(other.verifying_key().to_bytes().as_slice()),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(STEVE), cw20, &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::PubkeyMismatch { .. }
+    ));
+}
+
+#[test]
+fn test_snapshots() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    router.update_block(|block| block.height += 1);
+    let transfer_height = router.block_info().height;
+    let msg = ExecuteMsg::Transfer {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(100),
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    router.update_block(|block| block.height += 1);
+    let mint_height = router.block_info().height;
+    let msg = ExecuteMsg::Mint {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(500),
+    };
+    router
+        .execute_contract(Addr::unchecked(MINTER), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    router.update_block(|block| block.height += 1);
+    let burn_height = router.block_info().height;
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::new(200),
+    };
+    router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &msg, &[])
+        .unwrap();
+    router.update_block(|block| block.height += 1);
+
+    // A snapshot holds the state at the beginning of its block
+    assert_eq!(
+        query_balance_at(&router, &cw20, ROB, transfer_height),
+        Uint128::new(INITIAL_BALANCE)
+    );
+    assert_eq!(
+        query_balance_at(&router, &cw20, ROB, transfer_height + 1),
+        Uint128::new(INITIAL_BALANCE - 100)
+    );
+    assert_eq!(
+        query_balance_at(&router, &cw20, STEVE, transfer_height),
+        Uint128::zero()
+    );
+    assert_eq!(
+        query_balance_at(&router, &cw20, STEVE, mint_height),
+        Uint128::new(100)
+    );
+    assert_eq!(
+        query_balance_at(&router, &cw20, STEVE, burn_height),
+        Uint128::new(600)
+    );
+    assert_eq!(
+        query_balance_at(&router, &cw20, STEVE, burn_height + 1),
+        Uint128::new(400)
+    );
+
+    assert_eq!(
+        query_total_supply_at(&router, &cw20, mint_height),
+        Uint128::new(INITIAL_BALANCE)
+    );
+    assert_eq!(
+        query_total_supply_at(&router, &cw20, burn_height),
+        Uint128::new(INITIAL_BALANCE + 500)
+    );
+    assert_eq!(
+        query_total_supply_at(&router, &cw20, burn_height + 1),
+        Uint128::new(INITIAL_BALANCE + 300)
+    );
+}
+
+// Instantiates the cw20 wrapper charging a 1% transfer tax
+fn setup_taxed_contract(router: &mut App) -> Addr {
+    let code_id = router.store_code(contract_cw20());
+    router
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(DEPLOYER),
+            &InstantiateMsg {
+                tax_bps: 100,
+                tax_collector: Some(COLLECTOR.to_string()),
+                ..instantiate_msg()
+            },
+            &[],
+            "cw20",
+            None,
+        )
+        .unwrap()
+}
+
+#[test]
+fn test_tax_requires_collector() {
+    let mut router = App::default();
+    let code_id = router.store_code(contract_cw20());
+
+    let err = router
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(DEPLOYER),
+            &InstantiateMsg {
+                tax_bps: 100,
+                ..instantiate_msg()
+            },
+            &[],
+            "cw20",
+            None,
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidTax(_)
+    ));
+}
+
+#[test]
+fn test_tax() {
+    let mut router = App::default();
+    let cw20 = setup_taxed_contract(&mut router);
+
+    let res: TaxInfoResponse = router
+        .wrap()
+        .query_wasm_smart(&cw20, &QueryMsg::TaxInfo {})
+        .unwrap();
+    assert_eq!(
+        res,
+        TaxInfoResponse {
+            tax_bps: 100,
+            tax_collector: Some(COLLECTOR.to_string()),
+        }
+    );
+
+    // The recipient gets the amount minus the tax
+    let msg = ExecuteMsg::Transfer {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(10_000),
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(9_900));
+    assert_eq!(query_balance(&router, &cw20, COLLECTOR), Uint128::new(100));
+
+    // The allowance covers the tax
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: STEVE.to_string(),
+        amount: Uint128::new(5_000),
+        expires: None,
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+    let msg = ExecuteMsg::TransferFrom {
+        owner: ROB.to_string(),
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(5_000),
+    };
+    router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(14_850));
+    assert_eq!(query_balance(&router, &cw20, COLLECTOR), Uint128::new(150));
+    let res: AllowanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &cw20,
+            &QueryMsg::Allowance {
+                owner: ROB.to_string(),
+                spender: STEVE.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.allowance, Uint128::zero());
+
+    // The receiver of a send is notified of the net amount
+    let receiver_code_id = router.store_code(contract_receiver());
+    let receiver = router
+        .instantiate_contract(
+            receiver_code_id,
+            Addr::unchecked(DEPLOYER),
+            &Empty {},
+            &[],
+            "receiver",
+            None,
+        )
+        .unwrap();
+    let msg = ExecuteMsg::Send {
+        contract: receiver.to_string(),
+        amount: Uint128::new(1_000),
+        msg: Binary::default(),
+    };
+    let res = router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert!(res.has_event(&Event::new("wasm").add_attribute("received", "990")));
+    assert_eq!(
+        query_balance(&router, &cw20, receiver.as_str()),
+        Uint128::new(990)
+    );
+}
+
+#[test]
+fn test_tax_collector_checks() {
+    let mut router = App::default();
+    let cw20 = setup_taxed_contract(&mut router);
+    let transfer = ExecuteMsg::Transfer {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(10_000),
+    };
+
+    // A denied collector cannot receive the tax
+    let msg = ExecuteMsg::AddToDenyList {
+        address: COLLECTOR.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(COMPLIANCE_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap();
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &transfer, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Denied { .. }
+    ));
+    let msg = ExecuteMsg::RemoveFromDenyList {
+        address: COLLECTOR.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(COMPLIANCE_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    // Neither can a frozen one
+    let msg = ExecuteMsg::FreezeAccount {
+        address: COLLECTOR.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(PAUSER), cw20.clone(), &msg, &[])
+        .unwrap();
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &transfer, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Frozen { .. }
+    ));
+    assert_eq!(query_balance(&router, &cw20, COLLECTOR), Uint128::zero());
+}
+
+#[test]
+fn test_tax_exemption() {
+    let mut router = App::default();
+    let cw20 = setup_taxed_contract(&mut router);
+
+    // Only the minter manages exemptions
+    let msg = ExecuteMsg::SetTaxExemption {
+        address: ROB.to_string(),
+        exempt: true,
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    router
+        .execute_contract(Addr::unchecked(MINTER), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = ExecuteMsg::Transfer {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(10_000),
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(10_000));
+    assert_eq!(query_balance(&router, &cw20, COLLECTOR), Uint128::zero());
+
+    // Removing the tax stops charging it
+    let msg = ExecuteMsg::UpdateTax {
+        tax_bps: 0,
+        tax_collector: None,
+    };
+    router
+        .execute_contract(Addr::unchecked(MINTER), cw20.clone(), &msg, &[])
+        .unwrap();
+    let msg = ExecuteMsg::Transfer {
+        recipient: ROB.to_string(),
+        amount: Uint128::new(10_000),
+    };
+    router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::zero());
+}
+
+#[test]
+fn test_batch_transfer() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    let msg = ExecuteMsg::BatchTransfer {
+        transfers: vec![
+            TransferLeg {
+                recipient: STEVE.to_string(),
+                amount: Uint128::new(100),
+            },
+            TransferLeg {
+                recipient: COLLECTOR.to_string(),
+                amount: Uint128::new(200),
+            },
+        ],
+    };
+    let res = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert!(res.has_event(
+        &Event::new("wasm-transfer")
+            .add_attribute("from", ROB)
+            .add_attribute("to", STEVE)
+            .add_attribute("amount", "100")
+    ));
+    assert!(res.has_event(
+        &Event::new("wasm-transfer")
+            .add_attribute("to", COLLECTOR)
+            .add_attribute("amount", "200")
+    ));
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(100));
+    assert_eq!(query_balance(&router, &cw20, COLLECTOR), Uint128::new(200));
+    assert_eq!(
+        query_balance(&router, &cw20, ROB),
+        Uint128::new(INITIAL_BALANCE - 300)
+    );
+
+    // The whole batch must be covered by the balance
+    let msg = ExecuteMsg::BatchTransfer {
+        transfers: vec![
+            TransferLeg {
+                recipient: ROB.to_string(),
+                amount: Uint128::new(60),
+            },
+            TransferLeg {
+                recipient: COLLECTOR.to_string(),
+                amount: Uint128::new(60),
+            },
+        ],
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InsufficientBalance { .. }
+    ));
+
+    let msg = ExecuteMsg::BatchTransfer { transfers: vec![] };
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::EmptyBatch {}
+    ));
+
+    let msg = ExecuteMsg::BatchTransfer {
+        transfers: vec![
+            TransferLeg {
+                recipient: STEVE.to_string(),
+                amount: Uint128::new(1),
+            };
+            MAX_BATCH_SIZE + 1
+        ],
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20, &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::BatchTooLarge { .. }
+    ));
+}
+
+#[test]
+fn test_batch_send() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    let receiver_code_id = router.store_code(contract_receiver());
+    let receiver = router
+        .instantiate_contract(
+            receiver_code_id,
+            Addr::unchecked(DEPLOYER),
+            &Empty {},
+            &[],
+            "receiver",
+            None,
+        )
+        .unwrap();
+
+    // Every leg notifies its contract
+    let msg = ExecuteMsg::BatchSend {
+        sends: vec![
+            SendLeg {
+                contract: receiver.to_string(),
+                amount: Uint128::new(100),
+                msg: Binary::default(),
+            },
+            SendLeg {
+                contract: receiver.to_string(),
+                amount: Uint128::new(250),
+                msg: Binary::default(),
+            },
+        ],
+    };
+    let res = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert!(res.has_event(&Event::new("wasm").add_attribute("received", "100")));
+    assert!(res.has_event(&Event::new("wasm").add_attribute("received", "250")));
+    assert!(res.has_event(&Event::new("wasm-send").add_attribute("amount", "250")));
+    assert_eq!(
+        query_balance(&router, &cw20, receiver.as_str()),
+        Uint128::new(350)
+    );
+}
+
+#[test]
+fn test_receiver_allowlist() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    let receiver_code_id = router.store_code(contract_receiver());
+    let receiver = router
+        .instantiate_contract(
+            receiver_code_id,
+            Addr::unchecked(DEPLOYER),
+            &Empty {},
+            &[],
+            "receiver",
+            None,
+        )
+        .unwrap();
+    let send = ExecuteMsg::Send {
+        contract: receiver.to_string(),
+        amount: Uint128::new(100),
+        msg: Binary::default(),
+    };
+
+    // Any contract can be sent tokens until the allowlist is enabled
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &send, &[])
+        .unwrap();
+
+    let enable = ExecuteMsg::SetReceiverAllowlist { enabled: true };
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &enable, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    router
+        .execute_contract(
+            Addr::unchecked(COMPLIANCE_ADMIN),
+            cw20.clone(),
+            &enable,
+            &[],
+        )
+        .unwrap();
+
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &send, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::ReceiverNotAllowed { contract } if contract == receiver.as_str()
+    ));
+
+    // Spenders cannot send to a disallowed contract either
+    let allow = ExecuteMsg::IncreaseAllowance {
+        spender: STEVE.to_string(),
+        amount: Uint128::new(100),
+        expires: None,
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &allow, &[])
+        .unwrap();
+    let send_from = ExecuteMsg::SendFrom {
+        owner: ROB.to_string(),
+        contract: receiver.to_string(),
+        amount: Uint128::new(100),
+        msg: Binary::default(),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &send_from, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::ReceiverNotAllowed { .. }
+    ));
+
+    let add = ExecuteMsg::AddAllowedReceiver {
+        address: receiver.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(COMPLIANCE_ADMIN), cw20.clone(), &add, &[])
+        .unwrap();
+    let res: AllowedReceiversResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &cw20,
+            &QueryMsg::AllowedReceivers {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res,
+        AllowedReceiversResponse {
+            enabled: true,
+            receivers: vec![receiver.to_string()],
+        }
+    );
+
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &send, &[])
+        .unwrap();
+    router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &send_from, &[])
+        .unwrap();
+    assert_eq!(
+        query_balance(&router, &cw20, receiver.as_str()),
+        Uint128::new(300)
+    );
+
+    // Removed receivers are rejected again
+    let remove = ExecuteMsg::RemoveAllowedReceiver {
+        address: receiver.to_string(),
+    };
+    router
+        .execute_contract(
+            Addr::unchecked(COMPLIANCE_ADMIN),
+            cw20.clone(),
+            &remove,
+            &[],
+        )
+        .unwrap();
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &send, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::ReceiverNotAllowed { .. }
+    ));
+}
+
+// Instantiates the cw20 wrapper with the given emission limits
+fn setup_emission_contract(
+    router: &mut App,
+    emission_schedule: Option<EmissionSchedule>,
+    epoch_mint_limit: Option<EpochLimit>,
+) -> Result<Addr, ContractError> {
+    let code_id = router.store_code(contract_cw20());
+    router
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(DEPLOYER),
+            &InstantiateMsg {
+                emission_schedule,
+                epoch_mint_limit,
+                ..instantiate_msg()
+            },
+            &[],
+            "cw20",
+            None,
+        )
+        .map_err(|err| err.downcast().unwrap())
+}
+
+fn query_mintable_now(router: &App, cw20: &Addr) -> Option<Uint128> {
+    let res: MintableNowResponse = router
+        .wrap()
+        .query_wasm_smart(cw20, &QueryMsg::MintableNow {})
+        .unwrap();
+    res.mintable
+}
+
+fn mint(router: &mut App, cw20: &Addr, amount: u128) -> Result<(), ContractError> {
+    mint_as(router, cw20, MINTER, amount)
+}
+
+#[test]
+fn test_mint_unlimited_without_emission() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    assert_eq!(query_mintable_now(&router, &cw20), None);
+    mint(&mut router, &cw20, 1_000_000).unwrap();
+}
+
+#[test]
+fn test_linear_emission_with_epoch_limit() {
+    let mut router = App::default();
+    let start = router.block_info().time.seconds();
+    let cw20 = setup_emission_contract(
+        &mut router,
+        Some(EmissionSchedule::Linear {
+            start,
+            end: start + 1_000,
+            amount: Uint128::new(10_000),
+        }),
+        Some(EpochLimit {
+            duration: 100,
+            amount: Uint128::new(3_000),
+        }),
+    )
+    .unwrap();
+
+    // Nothing is unlocked at the start of the schedule
+    assert_eq!(query_mintable_now(&router, &cw20), Some(Uint128::zero()));
+    let err = mint(&mut router, &cw20, 1).unwrap_err();
+    assert!(matches!(err, ContractError::MintLimitExceeded { .. }));
+
+    // Half of the schedule is unlocked but the epoch limit applies
+    router.update_block(|block| block.time = block.time.plus_seconds(500));
+    assert_eq!(
+        query_mintable_now(&router, &cw20),
+        Some(Uint128::new(3_000))
+    );
+    mint(&mut router, &cw20, 3_000).unwrap();
+    assert_eq!(query_mintable_now(&router, &cw20), Some(Uint128::zero()));
+    let err = mint(&mut router, &cw20, 1).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::MintLimitExceeded { mintable } if mintable.is_zero()
+    ));
+
+    // The next epoch resets the epoch limit, the schedule still counts past mints
+    router.update_block(|block| block.time = block.time.plus_seconds(100));
+    assert_eq!(
+        query_mintable_now(&router, &cw20),
+        Some(Uint128::new(3_000))
+    );
+    router.update_block(|block| block.time = block.time.plus_seconds(1_000));
+    mint(&mut router, &cw20, 3_000).unwrap();
+    mint(&mut router, &cw20, 1).unwrap_err();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(6_000));
+}
+
+#[test]
+fn test_stepwise_emission() {
+    let mut router = App::default();
+    let start = router.block_info().time.seconds();
+
+    let err = setup_emission_contract(
+        &mut router,
+        Some(EmissionSchedule::Stepwise {
+            steps: vec![
+                EmissionStep {
+                    time: start + 20,
+                    amount: Uint128::new(200),
+                },
+                EmissionStep {
+                    time: start + 10,
+                    amount: Uint128::new(100),
+                },
+            ],
+        }),
+        None,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidEmission(_)));
+
+    let cw20 = setup_emission_contract(
+        &mut router,
+        Some(EmissionSchedule::Stepwise {
+            steps: vec![
+                EmissionStep {
+                    time: start + 10,
+                    amount: Uint128::new(100),
+                },
+                EmissionStep {
+                    time: start + 20,
+                    amount: Uint128::new(200),
+                },
+            ],
+        }),
+        None,
+    )
+    .unwrap();
+
+    router.update_block(|block| block.time = block.time.plus_seconds(15));
+    assert_eq!(query_mintable_now(&router, &cw20), Some(Uint128::new(100)));
+    mint(&mut router, &cw20, 60).unwrap();
+
+    router.update_block(|block| block.time = block.time.plus_seconds(5));
+    assert_eq!(query_mintable_now(&router, &cw20), Some(Uint128::new(240)));
+    let err = mint(&mut router, &cw20, 241).unwrap_err();
+    assert!(matches!(err, ContractError::MintLimitExceeded { .. }));
+}
+
+#[test]
+fn test_pause() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    // Only the pauser can pause
+    let err = router
+        .execute_contract(
+            Addr::unchecked(ROB),
+            cw20.clone(),
+            &ExecuteMsg::Pause {},
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    router
+        .execute_contract(
+            Addr::unchecked(PAUSER),
+            cw20.clone(),
+            &ExecuteMsg::Pause {},
+            &[],
+        )
+        .unwrap();
+
+    // Transfers, mints and burns are halted
+    let msgs = [
+        ExecuteMsg::Transfer {
+            recipient: STEVE.to_string(),
+            amount: Uint128::new(100),
+        },
+        ExecuteMsg::Burn {
+            amount: Uint128::new(100),
+        },
+    ];
+    for msg in msgs {
+        let err = router
+            .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Paused {}
+        ));
+    }
+    let err = mint(&mut router, &cw20, 100).unwrap_err();
+    assert!(matches!(err, ContractError::Paused {}));
+
+    // Allowances and queries keep working
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: STEVE.to_string(),
+        amount: Uint128::new(100),
+        expires: None,
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+    let res: PauseInfoResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &cw20,
+            &QueryMsg::PauseInfo {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert!(res.paused);
+
+    router
+        .execute_contract(
+            Addr::unchecked(PAUSER),
+            cw20.clone(),
+            &ExecuteMsg::Unpause {},
+            &[],
+        )
+        .unwrap();
+    mint(&mut router, &cw20, 100).unwrap();
+}
+
+#[test]
+fn test_freeze_account() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    let msg = ExecuteMsg::FreezeAccount {
+        address: STEVE.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(PAUSER), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    // A frozen account cannot receive, including through an allowance
+    let msg = ExecuteMsg::Transfer {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(100),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Frozen { address } if address == STEVE
+    ));
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: STEVE.to_string(),
+        amount: Uint128::new(100),
+        expires: None,
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+    let msg = ExecuteMsg::TransferFrom {
+        owner: ROB.to_string(),
+        recipient: COLLECTOR.to_string(),
+        amount: Uint128::new(100),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Frozen { .. }
+    ));
+
+    // Other accounts are unaffected
+    let msg = ExecuteMsg::Transfer {
+        recipient: COLLECTOR.to_string(),
+        amount: Uint128::new(100),
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    let res: PauseInfoResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &cw20,
+            &QueryMsg::PauseInfo {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res,
+        PauseInfoResponse {
+            pauser: Some(PAUSER.to_string()),
+            paused: false,
+            frozen: vec![STEVE.to_string()],
+        }
+    );
+
+    let msg = ExecuteMsg::UnfreezeAccount {
+        address: STEVE.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(PAUSER), cw20.clone(), &msg, &[])
+        .unwrap();
+    let msg = ExecuteMsg::Transfer {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(100),
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20, &msg, &[])
+        .unwrap();
+}
+
+fn query_top_holders(
+    router: &App,
+    cw20: &Addr,
+    limit: Option<u32>,
+    start_after: Option<String>,
+) -> Vec<(String, u128)> {
+    let res: TopHoldersResponse = router
+        .wrap()
+        .query_wasm_smart(cw20, &QueryMsg::TopHolders { limit, start_after })
+        .unwrap();
+    res.holders
+        .into_iter()
+        .map(|holder| (holder.address, holder.amount.u128()))
+        .collect()
+}
+
+#[test]
+fn test_top_holders() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    for (recipient, amount) in [(STEVE, 300), (COLLECTOR, 500), (PAUSER, 300)] {
+        let msg = ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount: Uint128::new(amount),
+        };
+        router
+            .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    // Equal balances are ranked by decreasing address
+    assert_eq!(
+        query_top_holders(&router, &cw20, None, None),
+        vec![
+            (ROB.to_string(), INITIAL_BALANCE - 1_100),
+            (COLLECTOR.to_string(), 500),
+            (STEVE.to_string(), 300),
+            (PAUSER.to_string(), 300),
+        ]
+    );
+    assert_eq!(
+        query_top_holders(&router, &cw20, Some(2), Some(COLLECTOR.to_string())),
+        vec![(STEVE.to_string(), 300), (PAUSER.to_string(), 300)]
+    );
+
+    // Emptied accounts leave the index, burns move the holder down
+    let msg = ExecuteMsg::Transfer {
+        recipient: ROB.to_string(),
+        amount: Uint128::new(300),
+    };
+    router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &msg, &[])
+        .unwrap();
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::new(200),
+    };
+    router
+        .execute_contract(Addr::unchecked(COLLECTOR), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(
+        query_top_holders(&router, &cw20, None, None),
+        vec![
+            (ROB.to_string(), INITIAL_BALANCE - 800),
+            (PAUSER.to_string(), 300),
+            (COLLECTOR.to_string(), 300),
+        ]
+    );
+
+    let res: HolderCountResponse = router
+        .wrap()
+        .query_wasm_smart(&cw20, &QueryMsg::HolderCount {})
+        .unwrap();
+    assert_eq!(res.count, 3);
+}
+
+#[test]
+fn test_wrapped_asset() {
+    let mut router = App::new(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(
+                storage,
+                &Addr::unchecked(DEPLOYER),
+                coins(INITIAL_BALANCE * 100 + 50, "uatom"),
+            )
+            .unwrap();
+    });
+    let (_code_id, plain) = setup_contract(&mut router);
+    let vault_code_id = router.store_code(contract_vault());
+    let vault = router
+        .instantiate_contract(
+            vault_code_id,
+            Addr::unchecked(DEPLOYER),
+            &Empty {},
+            &coins(INITIAL_BALANCE * 100 + 50, "uatom"),
+            "vault",
+            None,
+        )
+        .unwrap();
+
+    // Plain tokens wrap nothing
+    let res: WrappedAssetResponse = router
+        .wrap()
+        .query_wasm_smart(&plain, &QueryMsg::WrappedAsset {})
+        .unwrap();
+    assert_eq!(res.wrapped, None);
+    router
+        .wrap()
+        .query_wasm_smart::<BackingResponse>(&plain, &QueryMsg::Backing {})
+        .unwrap_err();
+
+    let code_id = router.store_code(contract_cw20());
+    let wrapped = WrappedAssetInfo {
+        denom: "uatom".to_string(),
+        vault: vault.to_string(),
+    };
+    let cw20 = router
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(DEPLOYER),
+            &InstantiateMsg {
+                wrapped: Some(wrapped.clone()),
+                ..instantiate_msg()
+            },
+            &[],
+            "cw20",
+            None,
+        )
+        .unwrap();
+
+    let res: WrappedAssetResponse = router
+        .wrap()
+        .query_wasm_smart(&cw20, &QueryMsg::WrappedAsset {})
+        .unwrap();
+    assert_eq!(res.wrapped, Some(wrapped));
+
+    // The vault balance is scaled to the receipt decimals, its dust backs nothing
+    let res: BackingResponse = router
+        .wrap()
+        .query_wasm_smart(&cw20, &QueryMsg::Backing {})
+        .unwrap();
+    assert_eq!(
+        res,
+        BackingResponse {
+            denom: "uatom".to_string(),
+            vault: vault.to_string(),
+            vault_balance: Uint128::new(INITIAL_BALANCE * 100 + 50),
+            backing: Uint128::new(INITIAL_BALANCE),
+            total_supply: Uint128::new(INITIAL_BALANCE),
+            fully_backed: true,
+        }
+    );
+
+    // Minting without depositing leaves the token under-backed
+    mint(&mut router, &cw20, 1).unwrap();
+    let res: BackingResponse = router
+        .wrap()
+        .query_wasm_smart(&cw20, &QueryMsg::Backing {})
+        .unwrap();
+    assert!(!res.fully_backed);
+}
+
+fn query_vesting_info(router: &App, cw20: &Addr, address: &str) -> VestingInfoResponse {
+    router
+        .wrap()
+        .query_wasm_smart(
+            cw20,
+            &QueryMsg::VestingInfo {
+                address: address.to_string(),
+            },
+        )
+        .unwrap()
+}
+
+fn transfer(
+    router: &mut App,
+    cw20: &Addr,
+    from: &str,
+    to: &str,
+    amount: u128,
+) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::Transfer {
+        recipient: to.to_string(),
+        amount: Uint128::new(amount),
+    };
+    router
+        .execute_contract(Addr::unchecked(from), cw20.clone(), &msg, &[])
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+fn claim_vesting(router: &mut App, cw20: &Addr, beneficiary: &str) -> Result<(), ContractError> {
+    router
+        .execute_contract(
+            Addr::unchecked(beneficiary),
+            cw20.clone(),
+            &ExecuteMsg::ClaimVesting {},
+            &[],
+        )
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+#[test]
+fn test_vesting() {
+    let mut router = App::default();
+    let start = router.block_info().time.seconds();
+    let code_id = router.store_code(contract_cw20());
+    let cw20 = router
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(DEPLOYER),
+            &InstantiateMsg {
+                vesting_balances: vec![
+                    VestingBalance {
+                        address: STEVE.to_string(),
+                        amount: Uint128::new(1_000),
+                        cliff: start + 100,
+                        start,
+                        end: start + 1_000,
+                        revocable: true,
+                    },
+                    VestingBalance {
+                        address: ROB.to_string(),
+                        amount: Uint128::new(500),
+                        cliff: start,
+                        start,
+                        end: start + 1_000,
+                        revocable: false,
+                    },
+                ],
+                vesting_admin: Some(VESTING_ADMIN.to_string()),
+                ..instantiate_msg()
+            },
+            &[],
+            "cw20",
+            None,
+        )
+        .unwrap();
+
+    // Vesting balances are held by the contract until claimed
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::zero());
+    assert_eq!(
+        query_balance(&router, &cw20, ROB),
+        Uint128::new(INITIAL_BALANCE)
+    );
+    assert_eq!(
+        query_balance(&router, &cw20, cw20.as_str()),
+        Uint128::new(1_500)
+    );
+    let err = claim_vesting(&mut router, &cw20, STEVE).unwrap_err();
+    assert!(matches!(err, ContractError::NothingToClaim {}));
+
+    // Half of the vesting can be claimed halfway through
+    router.update_block(|block| block.time = block.time.plus_seconds(500));
+    let res = query_vesting_info(&router, &cw20, STEVE);
+    assert_eq!(res.vested, Uint128::new(500));
+    assert_eq!(res.locked, Uint128::new(500));
+    assert_eq!(res.claimable, Uint128::new(500));
+    claim_vesting(&mut router, &cw20, STEVE).unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(500));
+    let err = claim_vesting(&mut router, &cw20, STEVE).unwrap_err();
+    assert!(matches!(err, ContractError::NothingToClaim {}));
+
+    // Only the admin claws back, only revocable vestings
+    let msg = ExecuteMsg::RevokeVesting {
+        address: STEVE.to_string(),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    let msg = ExecuteMsg::RevokeVesting {
+        address: ROB.to_string(),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(VESTING_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NotRevocable { .. }
+    ));
+
+    // A freeze halts the claw back like any other transfer
+    let freeze = ExecuteMsg::FreezeAccount {
+        address: STEVE.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(PAUSER), cw20.clone(), &freeze, &[])
+        .unwrap();
+    let msg = ExecuteMsg::RevokeVesting {
+        address: STEVE.to_string(),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(VESTING_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Frozen { .. }
+    ));
+    let unfreeze = ExecuteMsg::UnfreezeAccount {
+        address: STEVE.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(PAUSER), cw20.clone(), &unfreeze, &[])
+        .unwrap();
+
+    router
+        .execute_contract(Addr::unchecked(VESTING_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(500));
+    assert_eq!(
+        query_balance(&router, &cw20, VESTING_ADMIN),
+        Uint128::new(500)
+    );
+
+    // Vesting stops at the revocation
+    router.update_block(|block| block.time = block.time.plus_seconds(1_000));
+    let res = query_vesting_info(&router, &cw20, STEVE);
+    assert_eq!(res.vested, Uint128::new(500));
+    assert_eq!(res.locked, Uint128::zero());
+    assert_eq!(res.claimable, Uint128::zero());
+    let err = router
+        .execute_contract(Addr::unchecked(VESTING_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NotRevocable { .. }
+    ));
+
+    // Once fully vested, the whole allocation is released
+    claim_vesting(&mut router, &cw20, ROB).unwrap();
+    assert_eq!(
+        query_balance(&router, &cw20, ROB),
+        Uint128::new(INITIAL_BALANCE + 500)
+    );
+    assert_eq!(
+        query_balance(&router, &cw20, cw20.as_str()),
+        Uint128::zero()
+    );
+}
+
+fn mint_as(router: &mut App, cw20: &Addr, minter: &str, amount: u128) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::Mint {
+        recipient: STEVE.to_string(),
+        amount: Uint128::new(amount),
+    };
+    router
+        .execute_contract(Addr::unchecked(minter), cw20.clone(), &msg, &[])
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+#[test]
+fn test_vesting_follows_rebases() {
+    let mut router = App::default();
+    let start = router.block_info().time.seconds();
+    let vesting = |address: &str| VestingBalance {
+        address: address.to_string(),
+        amount: Uint128::new(1_000),
+        cliff: start,
+        start,
+        end: start + 1_000,
+        revocable: false,
+    };
+    let code_id = router.store_code(contract_cw20());
+    let cw20 = router
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(DEPLOYER),
+            &InstantiateMsg {
+                vesting_balances: vec![vesting(STEVE), vesting(COLLECTOR)],
+                rebasing: true,
+                ..instantiate_msg()
+            },
+            &[],
+            "cw20",
+            None,
+        )
+        .unwrap();
+
+    // The rebase yield goes to the beneficiaries
+    rebase(&mut router, &cw20, "1.5").unwrap();
+    router.update_block(|block| block.time = block.time.plus_seconds(500));
+    assert_eq!(
+        query_vesting_info(&router, &cw20, STEVE).claimable,
+        Uint128::new(750)
+    );
+    claim_vesting(&mut router, &cw20, STEVE).unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(750));
+
+    // Losses are shared as well, early claims cannot drain the later ones
+    rebase(&mut router, &cw20, "0.8").unwrap();
+    router.update_block(|block| block.time = block.time.plus_seconds(500));
+    claim_vesting(&mut router, &cw20, COLLECTOR).unwrap();
+    claim_vesting(&mut router, &cw20, STEVE).unwrap();
+    assert_eq!(query_balance(&router, &cw20, COLLECTOR), Uint128::new(800));
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(800));
+    assert_eq!(
+        query_balance(&router, &cw20, cw20.as_str()),
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn test_quota_minters() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+    let bridge = "bridge";
+
+    // Only the minter admin manages the minters
+    let msg = ExecuteMsg::AddMinter {
+        address: bridge.to_string(),
+        quota: Uint128::new(1_000),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(MINTER), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    router
+        .execute_contract(Addr::unchecked(MINTER_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    // Mints decrease the quota of the minter
+    mint_as(&mut router, &cw20, bridge, 600).unwrap();
+    let err = mint_as(&mut router, &cw20, bridge, 500).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::QuotaExceeded { remaining } if remaining == Uint128::new(400)
+    ));
+
+    let msg = ExecuteMsg::SetQuota {
+        address: bridge.to_string(),
+        quota: Uint128::new(1_000),
+    };
+    router
+        .execute_contract(Addr::unchecked(MINTER_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap();
+    mint_as(&mut router, &cw20, bridge, 500).unwrap();
+
+    // The cw20-base minter is not limited by quotas
+    mint_as(&mut router, &cw20, MINTER, 10_000).unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(11_100));
+
+    let res: MintersResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &cw20,
+            &QueryMsg::Minters {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res,
+        MintersResponse {
+            admin: Some(MINTER_ADMIN.to_string()),
+            minters: vec![MinterInfo {
+                address: bridge.to_string(),
+                remaining: Uint128::new(500),
+                minted: Uint128::new(1_100),
+            }],
+        }
+    );
+
+    let msg = ExecuteMsg::RemoveMinter {
+        address: bridge.to_string(),
+    };
+    router
+        .execute_contract(Addr::unchecked(MINTER_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap();
+    let err = mint_as(&mut router, &cw20, bridge, 1).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::Base(cw20_base::ContractError::Unauthorized {})
+    ));
+}
+
+#[test]
+fn test_quota_minters_share_emission_limits() {
+    let mut router = App::default();
+    let cw20 = setup_emission_contract(
+        &mut router,
+        None,
+        Some(EpochLimit {
+            duration: 100,
+            amount: Uint128::new(1_000),
+        }),
+    )
+    .unwrap();
+    let bridge = "bridge";
+    let msg = ExecuteMsg::AddMinter {
+        address: bridge.to_string(),
+        quota: Uint128::new(5_000),
+    };
+    router
+        .execute_contract(Addr::unchecked(MINTER_ADMIN), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    // Quota mints count against the epoch limit of the token
+    mint_as(&mut router, &cw20, bridge, 800).unwrap();
+    let err = mint_as(&mut router, &cw20, bridge, 300).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::MintLimitExceeded { mintable } if mintable == Uint128::new(200)
+    ));
+    let err = mint_as(&mut router, &cw20, MINTER, 300).unwrap_err();
+    assert!(matches!(err, ContractError::MintLimitExceeded { .. }));
+    assert_eq!(query_mintable_now(&router, &cw20), Some(Uint128::new(200)));
+}
+
+fn rebase(router: &mut App, cw20: &Addr, new_index: &str) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::Rebase {
+        new_index: new_index.parse().unwrap(),
+    };
+    router
+        .execute_contract(Addr::unchecked(MINTER), cw20.clone(), &msg, &[])
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+#[test]
+fn test_rebase_requires_rebasing_mode() {
+    let mut router = App::default();
+    let (_code_id, cw20) = setup_contract(&mut router);
+
+    let err = rebase(&mut router, &cw20, "1.5").unwrap_err();
+    assert!(matches!(err, ContractError::NotRebasing {}));
+}
+
+#[test]
+fn test_rebasing() {
+    let mut router = App::default();
+    let code_id = router.store_code(contract_cw20());
+    let cw20 = router
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(DEPLOYER),
+            &InstantiateMsg {
+                rebasing: true,
+                ..instantiate_msg()
+            },
+            &[],
+            "cw20",
+            None,
+        )
+        .unwrap();
+
+    let msg = ExecuteMsg::Rebase {
+        new_index: Decimal::percent(150),
+    };
+    let err = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    let err = rebase(&mut router, &cw20, "0").unwrap_err();
+    assert!(matches!(err, ContractError::ZeroIndex {}));
+
+    // Rebases are halted while paused
+    router
+        .execute_contract(
+            Addr::unchecked(PAUSER),
+            cw20.clone(),
+            &ExecuteMsg::Pause {},
+            &[],
+        )
+        .unwrap();
+    let err = rebase(&mut router, &cw20, "1.5").unwrap_err();
+    assert!(matches!(err, ContractError::Paused {}));
+    router
+        .execute_contract(
+            Addr::unchecked(PAUSER),
+            cw20.clone(),
+            &ExecuteMsg::Unpause {},
+            &[],
+        )
+        .unwrap();
+
+    // A rebase changes every balance and the supply at once
+    rebase(&mut router, &cw20, "1.5").unwrap();
+    assert_eq!(query_balance(&router, &cw20, ROB), Uint128::new(1_500_000));
+    let res: TokenInfoResponse = router
+        .wrap()
+        .query_wasm_smart(&cw20, &QueryMsg::TokenInfo {})
+        .unwrap();
+    assert_eq!(res.total_supply, Uint128::new(1_500_000));
+
+    // Amounts round down to whole shares, 10 tokens are 6 shares worth 9 tokens
+    transfer(&mut router, &cw20, ROB, STEVE, 10).unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(9));
+    assert_eq!(query_balance(&router, &cw20, ROB), Uint128::new(1_499_991));
+    transfer(&mut router, &cw20, ROB, STEVE, 3).unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(12));
+
+    // The receiver of a send is notified in tokens
+    let receiver_code_id = router.store_code(contract_receiver());
+    let receiver = router
+        .instantiate_contract(
+            receiver_code_id,
+            Addr::unchecked(DEPLOYER),
+            &Empty {},
+            &[],
+            "receiver",
+            None,
+        )
+        .unwrap();
+    let msg = ExecuteMsg::Send {
+        contract: receiver.to_string(),
+        amount: Uint128::new(30),
+        msg: Binary::default(),
+    };
+    let res = router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert!(res.has_event(&Event::new("wasm").add_attribute("received", "30")));
+
+    // Allowances are kept in tokens
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: STEVE.to_string(),
+        amount: Uint128::new(30),
+        expires: None,
+    };
+    router
+        .execute_contract(Addr::unchecked(ROB), cw20.clone(), &msg, &[])
+        .unwrap();
+
+    // A rebase cannot more than halve or double the index
+    let err = rebase(&mut router, &cw20, "0.3").unwrap_err();
+    assert!(matches!(err, ContractError::RebaseOutOfBounds { .. }));
+    let err = rebase(&mut router, &cw20, "3.1").unwrap_err();
+    assert!(matches!(err, ContractError::RebaseOutOfBounds { .. }));
+
+    // Balances shrink with the index, dust is rounded down
+    router.update_block(|block| block.height += 1);
+    let rebase_height = router.block_info().height;
+    rebase(&mut router, &cw20, "0.8").unwrap();
+    assert_eq!(query_balance(&router, &cw20, STEVE), Uint128::new(6));
+    assert_eq!(
+        query_balance(&router, &cw20, receiver.as_str()),
+        Uint128::new(16)
+    );
+    let res: TokenInfoResponse = router
+        .wrap()
+        .query_wasm_smart(&cw20, &QueryMsg::TokenInfo {})
+        .unwrap();
+    assert_eq!(res.total_supply, Uint128::new(800_000));
+
+    // Past balances keep the index that applied at the time
+    router.update_block(|block| block.height += 1);
+    assert_eq!(
+        query_balance_at(&router, &cw20, STEVE, rebase_height),
+        Uint128::new(12)
+    );
+    assert_eq!(
+        query_total_supply_at(&router, &cw20, rebase_height),
+        Uint128::new(1_500_000)
+    );
+    assert_eq!(
+        query_balance_at(&router, &cw20, STEVE, rebase_height + 1),
+        Uint128::new(6)
+    );
+
+    // The allowance is charged the tokens delivered, 30 tokens are 37 shares worth 29
+    let res: AllowanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &cw20,
+            &QueryMsg::Allowance {
+                owner: ROB.to_string(),
+                spender: STEVE.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.allowance, Uint128::new(30));
+    let msg = ExecuteMsg::TransferFrom {
+        owner: ROB.to_string(),
+        recipient: COLLECTOR.to_string(),
+        amount: Uint128::new(30),
+    };
+    router
+        .execute_contract(Addr::unchecked(STEVE), cw20.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(query_balance(&router, &cw20, COLLECTOR), Uint128::new(29));
+    let res: AllowanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &cw20,
+            &QueryMsg::Allowance {
+                owner: ROB.to_string(),
+                spender: STEVE.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.allowance, Uint128::new(1));
+}
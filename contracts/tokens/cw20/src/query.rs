@@ -1,20 +1,52 @@
+use crate::helpers::{locked, mintable_now, to_amount, vested};
+use crate::msg::{
+    AllowedReceiversResponse, BackingResponse, ComplianceInfoResponse, DenyListResponse,
+    HolderCountResponse, MintableNowResponse, MinterInfo, MintersResponse, PauseInfoResponse,
+    PermitNonceResponse, QueryMsg, RebaseIndexResponse, TaxExemptionsResponse, TaxInfoResponse,
+    TopHoldersResponse, TotalSupplyResponse, VaultInfoResponse, VaultQueryMsg, VestingInfoResponse,
+    WrappedAssetInfo, WrappedAssetResponse,
+};
+use crate::state::{
+    ALLOWED_RECEIVERS, BALANCE_SNAPSHOTS, COMPLIANCE, DENY_LIST, FROZEN, HOLDERS, HOLDER_COUNT,
+    MINTERS, MINTER_ADMIN, PAUSE, PERMIT_NONCES, REBASE_INDEX, SNAPSHOT_SEED_HEIGHT, TAX,
+    TAX_EXEMPT, TOTAL_SUPPLY_SNAPSHOTS, VESTING, WRAPPED,
+};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Binary, Deps, Env, StdResult};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Decimal, Deps, Env, Order, StdError, StdResult, Uint128,
+};
 use cw20_base::enumerable::{query_all_accounts, query_owner_allowances, query_spender_allowances};
-use cw20_base::msg::QueryMsg;
+use cw20_base::state::{BALANCES, TOKEN_INFO};
+use cw20_spec::{BalanceResponse, Cw20Coin};
+use cw_storage_plus::Bound;
 
 use cw20_base::allowances::query_allowance;
 use cw20_base::contract::{
     query_balance, query_download_logo, query_marketing_info, query_minter, query_token_info,
 };
 
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    // In rebasing mode cw20-base stores shares, they are reported as tokens
+    let index = REBASE_INDEX.may_load(deps.storage)?;
     match msg {
-        QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
-        QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
+        QueryMsg::Balance { address } => {
+            let mut res = query_balance(deps, address)?;
+            res.balance = to_amount(res.balance, index);
+            to_binary(&res)
+        }
+        QueryMsg::TokenInfo {} => {
+            let mut res = query_token_info(deps)?;
+            res.total_supply = to_amount(res.total_supply, index);
+            to_binary(&res)
+        }
         QueryMsg::Minter {} => to_binary(&query_minter(deps)?),
+        // Allowances are kept in tokens
         QueryMsg::Allowance { owner, spender } => {
             to_binary(&query_allowance(deps, owner, spender)?)
         }
@@ -38,5 +70,357 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             start_after,
             limit,
         )?),
+        QueryMsg::ComplianceInfo {} => to_binary(&query_compliance_info(deps)?),
+        QueryMsg::DenyList { start_after, limit } => {
+            to_binary(&query_deny_list(deps, start_after, limit)?)
+        }
+        QueryMsg::AllowedReceivers { start_after, limit } => {
+            to_binary(&query_allowed_receivers(deps, start_after, limit)?)
+        }
+        QueryMsg::PermitNonce { owner } => to_binary(&query_permit_nonce(deps, owner)?),
+        // Past shares are converted with the index that applied at the time
+        QueryMsg::BalanceAt { address, height } => {
+            let index = REBASE_INDEX.may_load_at_height(deps.storage, height)?;
+            let mut res = query_balance_at(deps, address, height)?;
+            res.balance = to_amount(res.balance, index);
+            to_binary(&res)
+        }
+        QueryMsg::TotalSupplyAt { height } => {
+            let index = REBASE_INDEX.may_load_at_height(deps.storage, height)?;
+            let mut res = query_total_supply_at(deps, height)?;
+            res.total_supply = to_amount(res.total_supply, index);
+            to_binary(&res)
+        }
+        QueryMsg::MintableNow {} => to_binary(&query_mintable_now(deps, env, index)?),
+        QueryMsg::PauseInfo { start_after, limit } => {
+            to_binary(&query_pause_info(deps, start_after, limit)?)
+        }
+        QueryMsg::TopHolders { limit, start_after } => {
+            let mut res = query_top_holders(deps, limit, start_after)?;
+            for holder in res.holders.iter_mut() {
+                holder.amount = to_amount(holder.amount, index);
+            }
+            to_binary(&res)
+        }
+        QueryMsg::HolderCount {} => to_binary(&query_holder_count(deps)?),
+        QueryMsg::WrappedAsset {} => to_binary(&query_wrapped_asset(deps)?),
+        QueryMsg::Backing {} => to_binary(&query_backing(deps, index)?),
+        QueryMsg::VestingInfo { address } => to_binary(&query_vesting_info(deps, env, address)?),
+        QueryMsg::Minters { start_after, limit } => {
+            to_binary(&query_minters(deps, start_after, limit)?)
+        }
+        QueryMsg::RebaseIndex {} => to_binary(&RebaseIndexResponse { index }),
+        QueryMsg::TaxInfo {} => to_binary(&query_tax_info(deps)?),
+        QueryMsg::TaxExemptions { start_after, limit } => {
+            to_binary(&query_tax_exemptions(deps, start_after, limit)?)
+        }
+    }
+}
+
+/// Returns the compliance admin and contract
+fn query_compliance_info(deps: Deps) -> StdResult<ComplianceInfoResponse> {
+    let compliance = COMPLIANCE.load(deps.storage)?;
+
+    Ok(ComplianceInfoResponse {
+        admin: compliance.admin.map(|admin| admin.to_string()),
+        contract: compliance.contract.map(|contract| contract.to_string()),
+    })
+}
+
+/// Returns the blocked addresses
+fn query_deny_list(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<DenyListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(Addr::unchecked);
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let addresses = DENY_LIST
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(String::from))
+        .collect::<StdResult<_>>()?;
+
+    Ok(DenyListResponse { addresses })
+}
+
+/// Returns whether the receiver allowlist is enabled and the allowed receivers
+fn query_allowed_receivers(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllowedReceiversResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(Addr::unchecked);
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let receivers = ALLOWED_RECEIVERS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(String::from))
+        .collect::<StdResult<_>>()?;
+
+    Ok(AllowedReceiversResponse {
+        enabled: COMPLIANCE.load(deps.storage)?.receiver_allowlist,
+        receivers,
+    })
+}
+
+/// Returns the nonce the next permit of the owner must use
+fn query_permit_nonce(deps: Deps, owner: String) -> StdResult<PermitNonceResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let nonce = PERMIT_NONCES
+        .may_load(deps.storage, &owner)?
+        .unwrap_or_default();
+
+    Ok(PermitNonceResponse { nonce })
+}
+
+/// Returns the balance of the address at the beginning of the block at `height`
+fn query_balance_at(deps: Deps, address: String, height: u64) -> StdResult<BalanceResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let balance = match BALANCE_SNAPSHOTS.may_load(deps.storage, &address)? {
+        Some(_) => BALANCE_SNAPSHOTS
+            .may_load_at_height(deps.storage, &address, height)?
+            .unwrap_or_default(),
+        // Accounts untouched since the migration still hold their balance from before it
+        None => match SNAPSHOT_SEED_HEIGHT.may_load(deps.storage)? {
+            Some(seed_height) if height > seed_height => BALANCES
+                .may_load(deps.storage, &address)?
+                .unwrap_or_default(),
+            _ => Uint128::zero(),
+        },
+    };
+
+    Ok(BalanceResponse { balance })
+}
+
+/// Returns the total supply at the beginning of the block at `height`
+fn query_total_supply_at(deps: Deps, height: u64) -> StdResult<TotalSupplyResponse> {
+    let total_supply = TOTAL_SUPPLY_SNAPSHOTS
+        .may_load_at_height(deps.storage, height)?
+        .unwrap_or_default();
+
+    Ok(TotalSupplyResponse { total_supply })
+}
+
+/// Returns the transfer tax settings
+fn query_tax_info(deps: Deps) -> StdResult<TaxInfoResponse> {
+    let tax = TAX.load(deps.storage)?;
+
+    Ok(TaxInfoResponse {
+        tax_bps: tax.tax_bps,
+        tax_collector: tax.collector.map(|collector| collector.to_string()),
+    })
+}
+
+/// Returns the addresses exempted from the transfer tax
+fn query_tax_exemptions(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TaxExemptionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(Addr::unchecked);
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let addresses = TAX_EXEMPT
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(String::from))
+        .collect::<StdResult<_>>()?;
+
+    Ok(TaxExemptionsResponse { addresses })
+}
+
+/// Returns the amount the minter can mint at the current block, within the cap
+fn query_mintable_now(
+    deps: Deps,
+    env: Env,
+    index: Option<Decimal>,
+) -> StdResult<MintableNowResponse> {
+    let info = TOKEN_INFO.load(deps.storage)?;
+    let by_cap = info
+        .get_cap()
+        .map(|cap| cap.saturating_sub(to_amount(info.total_supply, index)));
+    let by_emission = mintable_now(deps.storage, &env)?;
+
+    let mintable = match (by_cap, by_emission) {
+        (Some(cap), Some(emission)) => Some(cap.min(emission)),
+        (cap, emission) => cap.or(emission),
+    };
+
+    Ok(MintableNowResponse { mintable })
+}
+
+/// Returns the global pause state and the frozen accounts
+fn query_pause_info(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<PauseInfoResponse> {
+    let pause = PAUSE.load(deps.storage)?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(Addr::unchecked);
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let frozen = FROZEN
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(String::from))
+        .collect::<StdResult<_>>()?;
+
+    Ok(PauseInfoResponse {
+        pauser: pause.pauser.map(|pauser| pauser.to_string()),
+        paused: pause.paused,
+        frozen,
+    })
+}
+
+/// Returns the holders ranked by decreasing balance, then by decreasing address
+fn query_top_holders(
+    deps: Deps,
+    limit: Option<u32>,
+    start_after: Option<String>,
+) -> StdResult<TopHoldersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|address| -> StdResult<_> {
+            let address = deps.api.addr_validate(&address)?;
+            let balance = BALANCES
+                .may_load(deps.storage, &address)?
+                .unwrap_or_default();
+            Ok((balance.u128(), address))
+        })
+        .transpose()?;
+    let end = start_after
+        .as_ref()
+        .map(|(balance, address)| Bound::exclusive((*balance, address)));
+
+    let holders = HOLDERS
+        .keys(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(balance, address)| Cw20Coin {
+                address: address.to_string(),
+                amount: Uint128::new(balance),
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(TopHoldersResponse { holders })
+}
+
+/// Returns the number of accounts holding a non-zero balance
+fn query_holder_count(deps: Deps) -> StdResult<HolderCountResponse> {
+    let count = HOLDER_COUNT.may_load(deps.storage)?.unwrap_or_default();
+
+    Ok(HolderCountResponse { count })
+}
+
+/// Returns the native asset the token is a receipt for
+fn query_wrapped_asset(deps: Deps) -> StdResult<WrappedAssetResponse> {
+    let wrapped = WRAPPED
+        .may_load(deps.storage)?
+        .map(|wrapped| WrappedAssetInfo {
+            denom: wrapped.denom,
+            vault: wrapped.vault.to_string(),
+        });
+
+    Ok(WrappedAssetResponse { wrapped })
+}
+
+/// Compares the bank balance of the vault, in receipt units, with the total supply
+fn query_backing(deps: Deps, index: Option<Decimal>) -> StdResult<BackingResponse> {
+    let wrapped = WRAPPED
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("Token does not wrap an asset"))?;
+    let vault_balance = deps
+        .querier
+        .query_balance(&wrapped.vault, &wrapped.denom)?
+        .amount;
+    let vault_info: VaultInfoResponse = deps.querier.query_wasm_smart(
+        &wrapped.vault,
+        &VaultQueryMsg::VaultInfo {
+            denom: wrapped.denom.clone(),
+        },
+    )?;
+    let backing = to_receipt_units(vault_balance.saturating_sub(vault_info.dust), &vault_info)?;
+    let total_supply = to_amount(TOKEN_INFO.load(deps.storage)?.total_supply, index);
+
+    Ok(BackingResponse {
+        denom: wrapped.denom,
+        vault: wrapped.vault.to_string(),
+        vault_balance,
+        backing,
+        total_supply,
+        fully_backed: backing >= total_supply,
+    })
+}
+
+/// Converts a native amount into receipts the way the vault scales its bonds
+fn to_receipt_units(amount: Uint128, vault_info: &VaultInfoResponse) -> StdResult<Uint128> {
+    let ten = Uint128::new(10);
+    if vault_info.receipt_decimals >= vault_info.native_decimals {
+        let factor =
+            ten.checked_pow((vault_info.receipt_decimals - vault_info.native_decimals) as u32)?;
+        Ok(amount.checked_mul(factor)?)
+    } else {
+        let factor =
+            ten.checked_pow((vault_info.native_decimals - vault_info.receipt_decimals) as u32)?;
+        Ok(amount / factor)
     }
 }
+
+/// Returns the vesting schedule and progress of the address
+fn query_vesting_info(deps: Deps, env: Env, address: String) -> StdResult<VestingInfoResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let schedule = VESTING.may_load(deps.storage, &address)?;
+    let time = env.block.time.seconds();
+    let index = REBASE_INDEX.may_load(deps.storage)?;
+    let tokens = |shares| to_amount(shares, index);
+
+    Ok(VestingInfoResponse {
+        vested: schedule
+            .as_ref()
+            .map_or(Uint128::zero(), |schedule| tokens(vested(schedule, time))),
+        locked: schedule
+            .as_ref()
+            .map_or(Uint128::zero(), |schedule| tokens(locked(schedule, time))),
+        claimable: schedule.as_ref().map_or(Uint128::zero(), |schedule| {
+            tokens(vested(schedule, time) - schedule.claimed)
+        }),
+        schedule,
+    })
+}
+
+/// Returns the minters limited by a quota
+fn query_minters(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<MintersResponse> {
+    let admin = MINTER_ADMIN.may_load(deps.storage)?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(Addr::unchecked);
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let minters = MINTERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, quota)| MinterInfo {
+                address: address.to_string(),
+                remaining: quota.remaining,
+                minted: quota.minted,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(MintersResponse {
+        admin: admin.map(|admin| admin.to_string()),
+        minters,
+    })
+}
@@ -0,0 +1,192 @@
+use cosmwasm_std::{Addr, Decimal, Empty, Uint128};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ComplianceConfig {
+    /// The address that can manage the deny-list
+    pub admin: Option<Addr>,
+    /// The contract asked to approve every transfer
+    pub contract: Option<Addr>,
+    /// Only allowed receivers can be sent tokens with a hook
+    #[serde(default)]
+    pub receiver_allowlist: bool,
+}
+
+/// The compliance settings of the token
+pub const COMPLIANCE: Item<ComplianceConfig> = Item::new("compliance");
+
+/// Addresses that can neither send nor receive tokens
+pub const DENY_LIST: Map<&Addr, Empty> = Map::new("deny_list");
+
+/// Contracts that can be sent tokens while the receiver allowlist is enabled
+pub const ALLOWED_RECEIVERS: Map<&Addr, Empty> = Map::new("allowed_receivers");
+
+/// Nonce the next permit of each owner must use
+pub const PERMIT_NONCES: Map<&Addr, u64> = Map::new("permit_nonces");
+
+/// Balance of each account, checkpointed at every height it changes
+pub const BALANCE_SNAPSHOTS: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "balance_snapshots",
+    "balance_snapshots__checkpoints",
+    "balance_snapshots__changelog",
+    Strategy::EveryBlock,
+);
+
+/// Total supply, checkpointed at every height it changes
+pub const TOTAL_SUPPLY_SNAPSHOTS: SnapshotItem<Uint128> = SnapshotItem::new(
+    "total_supply_snapshots",
+    "total_supply_snapshots__checkpoints",
+    "total_supply_snapshots__changelog",
+    Strategy::EveryBlock,
+);
+
+/// Height of the migration that introduced the snapshots, balances held before it are
+/// checkpointed at that height the first time the account is touched
+pub const SNAPSHOT_SEED_HEIGHT: Item<u64> = Item::new("snapshot_seed_height");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct TaxConfig {
+    /// Share of every transfer credited to the collector, in basis points
+    pub tax_bps: u16,
+    /// The address receiving the tax
+    pub collector: Option<Addr>,
+}
+
+/// The transfer tax settings of the token
+pub const TAX: Item<TaxConfig> = Item::new("tax");
+
+/// Addresses whose transfers are not taxed
+pub const TAX_EXEMPT: Map<&Addr, Empty> = Map::new("tax_exempt");
+
+/// Cumulative amount the minter may have minted at a given block time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EmissionSchedule {
+    /// Unlocks `amount` linearly between `start` and `end`, in seconds
+    Linear {
+        start: u64,
+        end: u64,
+        amount: Uint128,
+    },
+    /// Unlocks the amount of each step once its time, in seconds, is reached
+    Stepwise { steps: Vec<EmissionStep> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmissionStep {
+    pub time: u64,
+    pub amount: Uint128,
+}
+
+/// Maximum amount minted within each epoch of `duration` seconds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochLimit {
+    pub duration: u64,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct EmissionConfig {
+    pub schedule: Option<EmissionSchedule>,
+    pub epoch_limit: Option<EpochLimit>,
+}
+
+/// The limits applied to the minter
+pub const EMISSION: Item<EmissionConfig> = Item::new("emission");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct MintTracker {
+    /// Amount minted since the schedule was set
+    pub minted: Uint128,
+    /// Index of the epoch `epoch_minted` refers to
+    pub epoch: u64,
+    /// Amount minted during `epoch`
+    pub epoch_minted: Uint128,
+}
+
+/// What the minter already minted
+pub const MINT_TRACKER: Item<MintTracker> = Item::new("mint_tracker");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PauseConfig {
+    /// The address that can pause the token and freeze accounts
+    pub pauser: Option<Addr>,
+    /// Whether every transfer, send, mint and burn is halted
+    pub paused: bool,
+}
+
+/// The pause state of the token
+pub const PAUSE: Item<PauseConfig> = Item::new("pause");
+
+/// Accounts that can neither move nor receive tokens until unfrozen
+pub const FROZEN: Map<&Addr, Empty> = Map::new("frozen");
+
+/// Holders keyed by balance then address, to rank them without an off-chain indexer
+pub const HOLDERS: Map<(u128, &Addr), Empty> = Map::new("holders");
+
+/// Number of accounts holding a non-zero balance
+pub const HOLDER_COUNT: Item<u64> = Item::new("holder_count");
+
+/// Last account indexed by `IndexHolders`, set while balances held before the holder
+/// index existed remain to be indexed
+pub const HOLDER_BACKFILL: Item<Option<Addr>> = Item::new("holder_backfill");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WrappedAsset {
+    /// The native denom the token is a receipt for
+    pub denom: String,
+    /// The vault holding the native tokens
+    pub vault: Addr,
+}
+
+/// The asset backing the token, when it is a vault receipt
+pub const WRAPPED: Item<WrappedAsset> = Item::new("wrapped");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingSchedule {
+    /// The amount vesting linearly from `start` to `end`, in seconds. Kept in shares in
+    /// rebasing mode so the vesting gains or loses with the rebases
+    pub amount: Uint128,
+    /// Nothing vests before the cliff
+    pub cliff: u64,
+    pub start: u64,
+    pub end: u64,
+    /// Whether the vesting admin can claw back the unvested amount
+    pub revocable: bool,
+    /// When the vesting was revoked, vesting stops at that time
+    pub revoked_at: Option<u64>,
+    /// Amount already released to the beneficiary, in shares in rebasing mode
+    #[serde(default)]
+    pub claimed: Uint128,
+}
+
+/// The vesting schedule of each beneficiary
+pub const VESTING: Map<&Addr, VestingSchedule> = Map::new("vesting");
+
+/// The address that can revoke vestings and receives the clawed back tokens
+pub const VESTING_ADMIN: Item<Addr> = Item::new("vesting_admin");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MinterQuota {
+    /// Amount the minter can still mint
+    pub remaining: Uint128,
+    /// Amount minted so far
+    pub minted: Uint128,
+}
+
+/// Minters besides the cw20-base minter, each limited by its quota
+pub const MINTERS: Map<&Addr, MinterQuota> = Map::new("minters");
+
+/// The address that manages the minters
+pub const MINTER_ADMIN: Item<Addr> = Item::new("minter_admin");
+
+/// Amount of tokens per share, only set in rebasing mode where balances are stored as shares.
+/// Checkpointed at every height it changes to convert the balance snapshots
+pub const REBASE_INDEX: SnapshotItem<Decimal> = SnapshotItem::new(
+    "rebase_index",
+    "rebase_index__checkpoints",
+    "rebase_index__changelog",
+    Strategy::EveryBlock,
+);
@@ -1,27 +1,38 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+    coin, to_binary, Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Empty, Env, HexBinary,
+    MessageInfo, Response, StdResult, Storage, Timestamp, Uint128,
 };
 use cw2::set_contract_version;
 
 use crate::ContractError;
-use cw721::ContractInfoResponse;
-use cw721_base::ContractError as BaseError;
+use cw721_base::state::TokenInfo;
+use cw721_spec::ContractInfoResponse;
+use cw_utils::{must_pay, nonpayable};
+use sha2::{Digest, Sha256};
 use url::Url;
 
 use crate::msg::{
-    CollectionInfoResponse, ExecuteMsg, InstantiateMsg, QueryMsg, RoyaltyInfoResponse,
+    AllowlistInfoResponse, CheckRoyaltiesResponse, CollectionInfoResponse, ExecuteMsg,
+    InstantiateMsg, PlatformFeeResponse, QueryMsg, RoyaltiesInfoResponse, RoyaltyInfoResponse,
+    SaleInfoResponse,
+};
+use crate::state::{
+    AllowlistConfig, CollectionInfo, Extension, Metadata, PlatformFee, RoyaltyInfo, SaleConfig,
+    ALLOWLIST, ALLOWLIST_MINTS, COLLECTION_INFO, FROZEN_COLLECTION_INFO, ROYALTY_UPDATED_AT, SALE,
+    SALE_MINTED, SALE_MINTS, SALE_NEXT_TOKEN_ID,
 };
-use crate::state::{CollectionInfo, RoyaltyInfo, COLLECTION_INFO};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:pg-721";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const MAX_DESCRIPTION_LENGTH: u32 = 512;
+const MAX_ROYALTY_SHARE_DELTA_PCT: u64 = 2;
+const ROYALTY_SHARE_INCREASE_INTERVAL: u64 = 24 * 60 * 60;
 
-pub type Pg721Contract<'a> = cw721_base::Cw721Contract<'a, Empty, Empty, Empty, Empty>;
+pub type Pg721Contract<'a> = cw721_base::Cw721Contract<'a, Extension, Empty, Empty, Empty>;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -47,9 +58,7 @@ pub fn instantiate(
         .save(deps.storage, &minter)?;
 
     // pg721 instantiation
-    if msg.collection_info.description.len() > MAX_DESCRIPTION_LENGTH as usize {
-        return Err(ContractError::DescriptionTooLong {});
-    }
+    validate_description(&msg.collection_info.description)?;
 
     let image = Url::parse(&msg.collection_info.image)?;
 
@@ -58,10 +67,7 @@ pub fn instantiate(
     }
 
     let royalty_info: Option<RoyaltyInfo> = match msg.collection_info.royalty_info {
-        Some(royalty_info) => Some(RoyaltyInfo {
-            payment_address: deps.api.addr_validate(&royalty_info.payment_address)?,
-            share: royalty_info.share_validate()?,
-        }),
+        Some(royalty_info) => Some(validate_royalty_info(deps.as_ref(), royalty_info)?),
         None => None,
     };
 
@@ -77,6 +83,13 @@ pub fn instantiate(
 
     COLLECTION_INFO.save(deps.storage, &collection_info)?;
 
+    if let Some(sale) = msg.sale {
+        let sale = validate_sale(deps.as_ref(), sale)?;
+        SALE.save(deps.storage, &sale)?;
+        SALE_MINTED.save(deps.storage, &0)?;
+        SALE_NEXT_TOKEN_ID.save(deps.storage, &1)?;
+    }
+
     Ok(Response::default()
         .add_attribute("action", "instantiate")
         .add_attribute("contract_name", CONTRACT_NAME)
@@ -90,14 +103,543 @@ pub fn execute(
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> Result<Response, BaseError> {
-    Pg721Contract::default().execute(deps, env, info, msg)
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateCollectionInfo {
+            description,
+            image,
+            external_link,
+            royalty_info,
+            clear_external_link,
+        } => execute_update_collection_info(
+            deps,
+            env,
+            info,
+            description,
+            image,
+            external_link,
+            royalty_info,
+            clear_external_link,
+        ),
+        ExecuteMsg::FreezeCollectionInfo {} => execute_freeze_collection_info(deps, env, info),
+        ExecuteMsg::SetAllowlist {
+            merkle_root,
+            start_time,
+            end_time,
+            price,
+        } => execute_set_allowlist(deps, env, info, merkle_root, start_time, end_time, price),
+        ExecuteMsg::MintAllowlist { proof, allocation } => {
+            execute_mint_allowlist(deps, env, info, proof, allocation)
+        }
+        ExecuteMsg::MintPublic {} => execute_mint_public(deps, env, info),
+        ExecuteMsg::Withdraw {} => execute_withdraw(deps, env, info),
+        ExecuteMsg::Mint(ref mint_msg) => {
+            if let Some(ref metadata) = mint_msg.extension {
+                validate_metadata(metadata)?;
+            }
+            if let Some(sale) = SALE.may_load(deps.storage)? {
+                count_mint(deps.storage, &sale)?;
+            }
+            Ok(Pg721Contract::default().execute(deps, env, info, msg.into())?)
+        }
+        _ => Ok(Pg721Contract::default().execute(deps, env, info, msg.into())?),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_update_collection_info(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    description: Option<String>,
+    image: Option<String>,
+    external_link: Option<String>,
+    royalty_info: Option<RoyaltyInfoResponse>,
+    clear_external_link: bool,
+) -> Result<Response, ContractError> {
+    let mut collection_info = COLLECTION_INFO.load(deps.storage)?;
+
+    if collection_info.creator != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if FROZEN_COLLECTION_INFO
+        .may_load(deps.storage)?
+        .unwrap_or(false)
+    {
+        return Err(ContractError::CollectionInfoFrozen {});
+    }
+
+    if let Some(description) = description {
+        validate_description(&description)?;
+        collection_info.description = description;
+    }
+
+    if let Some(image) = image {
+        Url::parse(&image)?;
+        collection_info.image = image;
+    }
+
+    if clear_external_link {
+        collection_info.external_link = None;
+    } else if let Some(external_link) = external_link {
+        Url::parse(&external_link)?;
+        collection_info.external_link = Some(external_link);
+    }
+
+    if let Some(royalty_info) = royalty_info {
+        let royalty_info = validate_royalty_info(deps.as_ref(), royalty_info)?;
+        let old_share = collection_info
+            .royalty_info
+            .as_ref()
+            .map_or(Decimal::zero(), |royalty_info| royalty_info.share);
+
+        // share increases are limited in size and frequency so that buyers are not surprised
+        if royalty_info.share > old_share {
+            let max = Decimal::percent(MAX_ROYALTY_SHARE_DELTA_PCT);
+            if royalty_info.share - old_share > max {
+                return Err(ContractError::RoyaltyShareIncreaseTooLarge { max });
+            }
+
+            if let Some(updated_at) = ROYALTY_UPDATED_AT.may_load(deps.storage)? {
+                let time = updated_at.plus_seconds(ROYALTY_SHARE_INCREASE_INTERVAL);
+                if env.block.time < time {
+                    return Err(ContractError::RoyaltyShareIncreaseTooSoon { time });
+                }
+            }
+
+            ROYALTY_UPDATED_AT.save(deps.storage, &env.block.time)?;
+        }
+
+        collection_info.royalty_info = Some(royalty_info);
+    }
+
+    COLLECTION_INFO.save(deps.storage, &collection_info)?;
+
+    Ok(Response::new().add_attribute("action", "update_collection_info"))
+}
+
+pub fn execute_freeze_collection_info(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let collection_info = COLLECTION_INFO.load(deps.storage)?;
+
+    if collection_info.creator != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    FROZEN_COLLECTION_INFO.save(deps.storage, &true)?;
+
+    Ok(Response::new().add_attribute("action", "freeze_collection_info"))
+}
+
+pub fn execute_set_allowlist(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    merkle_root: HexBinary,
+    start_time: Timestamp,
+    end_time: Timestamp,
+    price: Coin,
+) -> Result<Response, ContractError> {
+    let collection_info = COLLECTION_INFO.load(deps.storage)?;
+
+    if collection_info.creator != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let sale = SALE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoSale {})?;
+    let allowlist = ALLOWLIST.may_load(deps.storage)?;
+    // moving the phase once it started would halt the sale
+    if sale.start_time <= env.block.time
+        || allowlist
+            .as_ref()
+            .is_some_and(|allowlist| allowlist.start_time <= env.block.time)
+    {
+        return Err(ContractError::InvalidAllowlist(String::from(
+            "the sale has already started",
+        )));
+    }
+    if merkle_root.len() != 32 {
+        return Err(ContractError::InvalidAllowlist(String::from(
+            "merkle_root must be a sha256 hash",
+        )));
+    }
+    if end_time <= start_time {
+        return Err(ContractError::InvalidAllowlist(String::from(
+            "end_time must be after start_time",
+        )));
+    }
+    if sale
+        .end_time
+        .is_some_and(|sale_end_time| end_time > sale_end_time)
+    {
+        return Err(ContractError::InvalidAllowlist(String::from(
+            "end_time must not be after the end of the sale",
+        )));
+    }
+    // withdraw only pays out the sale denom
+    if price.denom != sale.price.denom {
+        return Err(ContractError::InvalidAllowlist(format!(
+            "price must be paid in {}",
+            sale.price.denom
+        )));
+    }
+
+    // allocations of the old root do not carry over
+    if allowlist.is_some_and(|allowlist| allowlist.merkle_root != merkle_root) {
+        ALLOWLIST_MINTS.clear(deps.storage);
+    }
+    ALLOWLIST.save(
+        deps.storage,
+        &AllowlistConfig {
+            merkle_root: merkle_root.clone(),
+            start_time,
+            end_time,
+            price,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_allowlist")
+        .add_attribute("merkle_root", merkle_root.to_hex()))
+}
+
+pub fn execute_mint_allowlist(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proof: Vec<HexBinary>,
+    allocation: u32,
+) -> Result<Response, ContractError> {
+    let sale = SALE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoSale {})?;
+    let allowlist = ALLOWLIST
+        .may_load(deps.storage)?
+        .ok_or(ContractError::AllowlistNotActive {})?;
+
+    if env.block.time < allowlist.start_time || env.block.time >= allowlist.end_time {
+        return Err(ContractError::AllowlistNotActive {});
+    }
+    if sale
+        .end_time
+        .is_some_and(|end_time| env.block.time >= end_time)
+    {
+        return Err(ContractError::SaleEnded {});
+    }
+
+    check_payment(&info, &allowlist.price)?;
+    verify_proof(&allowlist.merkle_root, &proof, &info.sender, allocation)?;
+
+    let mints = ALLOWLIST_MINTS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if mints >= allocation {
+        return Err(ContractError::MintLimitReached { limit: allocation });
+    }
+    ALLOWLIST_MINTS.save(deps.storage, &info.sender, &(mints + 1))?;
+
+    let token_id = mint_next(deps, &sale, &info.sender)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "mint_allowlist")
+        .add_attribute("owner", info.sender)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn execute_mint_public(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let sale = SALE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoSale {})?;
+
+    // the public phase starts once the allowlist phase ends
+    let start_time = match ALLOWLIST.may_load(deps.storage)? {
+        Some(allowlist) => sale.start_time.max(allowlist.end_time),
+        None => sale.start_time,
+    };
+    if env.block.time < start_time {
+        return Err(ContractError::SaleNotStarted { start_time });
+    }
+    if sale
+        .end_time
+        .is_some_and(|end_time| env.block.time >= end_time)
+    {
+        return Err(ContractError::SaleEnded {});
+    }
+
+    check_payment(&info, &sale.price)?;
+
+    let mints = SALE_MINTS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if mints >= sale.per_address_limit {
+        return Err(ContractError::MintLimitReached {
+            limit: sale.per_address_limit,
+        });
+    }
+    SALE_MINTS.save(deps.storage, &info.sender, &(mints + 1))?;
+
+    let token_id = mint_next(deps, &sale, &info.sender)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "mint_public")
+        .add_attribute("owner", info.sender)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn execute_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let collection_info = COLLECTION_INFO.load(deps.storage)?;
+
+    if collection_info.creator != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let sale = SALE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoSale {})?;
+    let denom = sale.price.denom;
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &denom)?
+        .amount;
+    if balance.is_zero() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+
+    let mut res = Response::new().add_attribute("action", "withdraw");
+    let mut proceeds = balance;
+    if let Some(platform_fee) = sale.platform_fee {
+        let fee = balance * platform_fee.share;
+        if !fee.is_zero() {
+            proceeds -= fee;
+            res = res
+                .add_message(BankMsg::Send {
+                    to_address: platform_fee.address.to_string(),
+                    amount: vec![coin(fee.u128(), &denom)],
+                })
+                .add_attribute("platform_fee", fee);
+        }
+    }
+    if !proceeds.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: collection_info.creator,
+            amount: vec![coin(proceeds.u128(), &denom)],
+        });
+    }
+
+    Ok(res.add_attribute("proceeds", proceeds))
+}
+
+/// Counts a mint against the max supply of the sale
+fn count_mint(
+    storage: &mut dyn Storage,
+    sale: &SaleConfig<PlatformFee>,
+) -> Result<(), ContractError> {
+    let minted = SALE_MINTED.load(storage)?;
+    if minted >= sale.max_supply {
+        return Err(ContractError::SoldOut {});
+    }
+    SALE_MINTED.save(storage, &(minted + 1))?;
+
+    Ok(())
+}
+
+/// Mints the next free token id of the sale to `owner` and returns it
+fn mint_next(
+    deps: DepsMut,
+    sale: &SaleConfig<PlatformFee>,
+    owner: &Addr,
+) -> Result<String, ContractError> {
+    count_mint(deps.storage, sale)?;
+
+    // the minter can mint any id, those it took are skipped
+    let contract = Pg721Contract::default();
+    let mut next_id = SALE_NEXT_TOKEN_ID.load(deps.storage)?;
+    while contract.tokens.has(deps.storage, &next_id.to_string()) {
+        next_id += 1;
+    }
+    SALE_NEXT_TOKEN_ID.save(deps.storage, &(next_id + 1))?;
+
+    let token_id = next_id.to_string();
+    let token = TokenInfo {
+        owner: owner.clone(),
+        approvals: vec![],
+        token_uri: Some(format!(
+            "{}/{}",
+            sale.base_token_uri.trim_end_matches('/'),
+            token_id
+        )),
+        extension: None,
+    };
+
+    contract.tokens.save(deps.storage, &token_id, &token)?;
+    contract.increment_tokens(deps.storage)?;
+
+    Ok(token_id)
+}
+
+/// Checks that `sha256(address || allocation as big-endian u32)` is a leaf of the tree.
+/// Pairs of nodes are sorted before being hashed, so proofs carry no left/right flags
+fn verify_proof(
+    merkle_root: &HexBinary,
+    proof: &[HexBinary],
+    address: &Addr,
+    allocation: u32,
+) -> Result<(), ContractError> {
+    let leaf: [u8; 32] = Sha256::new()
+        .chain_update(address.as_bytes())
+        .chain_update(allocation.to_be_bytes())
+        .finalize()
+        .into();
+
+    let root = proof.iter().try_fold(leaf, |node, sibling| {
+        let sibling: [u8; 32] = sibling
+            .as_slice()
+            .try_into()
+            .map_err(|_| ContractError::InvalidProof {})?;
+        let (first, second) = if node <= sibling {
+            (node, sibling)
+        } else {
+            (sibling, node)
+        };
+        Ok::<_, ContractError>(
+            Sha256::new()
+                .chain_update(first)
+                .chain_update(second)
+                .finalize()
+                .into(),
+        )
+    })?;
+
+    if root.as_slice() != merkle_root.as_slice() {
+        return Err(ContractError::InvalidProof {});
+    }
+
+    Ok(())
+}
+
+/// Checks that exactly `price` was paid
+fn check_payment(info: &MessageInfo, price: &Coin) -> Result<(), ContractError> {
+    if price.amount.is_zero() {
+        nonpayable(info)?;
+    } else if must_pay(info, &price.denom)? != price.amount {
+        return Err(ContractError::IncorrectPayment {
+            expected: price.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_sale(
+    deps: Deps,
+    sale: SaleConfig<PlatformFeeResponse>,
+) -> Result<SaleConfig<PlatformFee>, ContractError> {
+    if sale.max_supply == 0 {
+        return Err(ContractError::InvalidSale(String::from(
+            "max_supply must be positive",
+        )));
+    }
+    if sale.per_address_limit == 0 {
+        return Err(ContractError::InvalidSale(String::from(
+            "per_address_limit must be positive",
+        )));
+    }
+    if sale
+        .end_time
+        .is_some_and(|end_time| end_time <= sale.start_time)
+    {
+        return Err(ContractError::InvalidSale(String::from(
+            "end_time must be after start_time",
+        )));
+    }
+    Url::parse(&sale.base_token_uri)?;
+
+    let platform_fee = match sale.platform_fee {
+        Some(platform_fee) => {
+            if platform_fee.share > Decimal::one() {
+                return Err(ContractError::InvalidSale(String::from(
+                    "platform fee share cannot exceed 1",
+                )));
+            }
+            Some(PlatformFee {
+                address: deps.api.addr_validate(&platform_fee.address)?,
+                share: platform_fee.share,
+            })
+        }
+        None => None,
+    };
+
+    Ok(SaleConfig {
+        price: sale.price,
+        max_supply: sale.max_supply,
+        per_address_limit: sale.per_address_limit,
+        start_time: sale.start_time,
+        end_time: sale.end_time,
+        base_token_uri: sale.base_token_uri,
+        platform_fee,
+    })
+}
+
+fn validate_description(description: &str) -> Result<(), ContractError> {
+    if description.len() > MAX_DESCRIPTION_LENGTH as usize {
+        return Err(ContractError::DescriptionTooLong {});
+    }
+
+    Ok(())
+}
+
+fn validate_metadata(metadata: &Metadata) -> Result<(), ContractError> {
+    for url in [
+        &metadata.image,
+        &metadata.animation_url,
+        &metadata.external_url,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        Url::parse(url)?;
+    }
+
+    Ok(())
+}
+
+fn validate_royalty_info(
+    deps: Deps,
+    royalty_info: RoyaltyInfoResponse,
+) -> Result<RoyaltyInfo, ContractError> {
+    Ok(RoyaltyInfo {
+        payment_address: deps.api.addr_validate(&royalty_info.payment_address)?,
+        share: royalty_info.share_validate()?,
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::CollectionInfo {} => to_binary(&query_config(deps)?),
+        QueryMsg::RoyaltyInfo {
+            token_id,
+            sale_price,
+        } => to_binary(&query_royalty_info(deps, token_id, sale_price)?),
+        QueryMsg::CheckRoyalties {} => to_binary(&query_check_royalties(deps)?),
+        QueryMsg::SaleInfo {} => to_binary(&query_sale_info(deps)?),
+        QueryMsg::AllowlistInfo {} => to_binary(&AllowlistInfoResponse {
+            allowlist: ALLOWLIST.may_load(deps.storage)?,
+        }),
         _ => Pg721Contract::default().query(deps, env, msg.into()),
     }
 }
@@ -122,13 +664,67 @@ fn query_config(deps: Deps) -> StdResult<CollectionInfoResponse> {
     })
 }
 
+fn query_royalty_info(
+    deps: Deps,
+    token_id: String,
+    sale_price: Uint128,
+) -> StdResult<RoyaltiesInfoResponse> {
+    // royalties are set for the whole collection, but the token must exist
+    Pg721Contract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+
+    let info = COLLECTION_INFO.load(deps.storage)?;
+
+    Ok(match info.royalty_info {
+        Some(royalty_info) => RoyaltiesInfoResponse {
+            address: royalty_info.payment_address.to_string(),
+            royalty_amount: sale_price * royalty_info.share,
+        },
+        None => RoyaltiesInfoResponse {
+            address: String::new(),
+            royalty_amount: Uint128::zero(),
+        },
+    })
+}
+
+fn query_check_royalties(deps: Deps) -> StdResult<CheckRoyaltiesResponse> {
+    let info = COLLECTION_INFO.load(deps.storage)?;
+
+    Ok(CheckRoyaltiesResponse {
+        royalty_payments: info.royalty_info.is_some(),
+    })
+}
+
+fn query_sale_info(deps: Deps) -> StdResult<SaleInfoResponse> {
+    let sale = SALE.load(deps.storage)?;
+    let minted = SALE_MINTED.load(deps.storage)?;
+
+    Ok(SaleInfoResponse {
+        price: sale.price,
+        max_supply: sale.max_supply,
+        per_address_limit: sale.per_address_limit,
+        start_time: sale.start_time,
+        end_time: sale.end_time,
+        platform_fee: sale.platform_fee.map(|platform_fee| PlatformFeeResponse {
+            address: platform_fee.address.to_string(),
+            share: platform_fee.share,
+        }),
+        minted,
+        remaining: sale.max_supply - minted,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use crate::state::CollectionInfo;
+    use crate::state::Trait;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary, Attribute, Decimal};
+    use cosmwasm_std::{coins, from_binary, Attribute, Decimal, SubMsg, Timestamp};
+    use cw721_base::MintMsg;
+    use cw721_spec::NftInfoResponse;
 
     const NATIVE_DENOM: &str = "ujunox";
 
@@ -144,8 +740,9 @@ mod tests {
                 description: String::from("Passage Monkeys"),
                 image: image.clone(),
                 external_link: Some("https://example.com/external.html".to_string()),
-                royalty_info: royalty_info,
+                royalty_info,
             },
+            sale: None,
         };
         let info = mock_info("creator", &coins(0, NATIVE_DENOM));
         let res = instantiate(deps, mock_env(), info.clone(), msg).unwrap();
@@ -195,4 +792,645 @@ mod tests {
             value.royalty_info
         );
     }
+
+    #[test]
+    fn royalty_info() {
+        let mut deps = mock_dependencies();
+        setup_contract(
+            deps.as_mut(),
+            Some(RoyaltyInfoResponse {
+                payment_address: String::from("creator"),
+                share: Decimal::percent(10),
+            }),
+        );
+
+        let msg = ExecuteMsg::Mint(MintMsg {
+            token_id: String::from("1"),
+            owner: String::from("owner"),
+            token_uri: None,
+            extension: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::CheckRoyalties {}).unwrap();
+        let value: CheckRoyaltiesResponse = from_binary(&res).unwrap();
+        assert!(value.royalty_payments);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RoyaltyInfo {
+                token_id: String::from("1"),
+                sale_price: Uint128::new(1_005),
+            },
+        )
+        .unwrap();
+        let value: RoyaltiesInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            RoyaltiesInfoResponse {
+                address: String::from("creator"),
+                royalty_amount: Uint128::new(100),
+            },
+            value
+        );
+
+        // unknown tokens have no royalty info
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RoyaltyInfo {
+                token_id: String::from("2"),
+                sale_price: Uint128::new(1_000),
+            },
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn royalty_info_no_royalties() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), None);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::CheckRoyalties {}).unwrap();
+        let value: CheckRoyaltiesResponse = from_binary(&res).unwrap();
+        assert!(!value.royalty_payments);
+    }
+
+    #[test]
+    fn update_collection_info() {
+        let mut deps = mock_dependencies();
+        setup_contract(
+            deps.as_mut(),
+            Some(RoyaltyInfoResponse {
+                payment_address: String::from("creator"),
+                share: Decimal::percent(5),
+            }),
+        );
+
+        let update = |share| ExecuteMsg::UpdateCollectionInfo {
+            description: Some(String::from("Passage Apes")),
+            image: None,
+            external_link: None,
+            royalty_info: Some(RoyaltyInfoResponse {
+                payment_address: String::from("creator"),
+                share,
+            }),
+            clear_external_link: false,
+        };
+
+        // only the creator can update
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("minter", &[]),
+            update(Decimal::percent(5)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateCollectionInfo {
+                description: None,
+                image: Some(String::from("not a url")),
+                external_link: None,
+                royalty_info: None,
+                clear_external_link: false,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Parse(_)));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            update(Decimal::percent(8)),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::RoyaltyShareIncreaseTooLarge { .. }
+        ));
+
+        let mut env = mock_env();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            update(Decimal::percent(7)),
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::CollectionInfo {}).unwrap();
+        let value: CollectionInfoResponse = from_binary(&res).unwrap();
+        assert_eq!("Passage Apes", value.description);
+        assert_eq!(Decimal::percent(7), value.royalty_info.unwrap().share);
+
+        // the share can decrease at any time, but only increase once a day
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            update(Decimal::percent(6)),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            update(Decimal::percent(7)),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::RoyaltyShareIncreaseTooSoon { .. }
+        ));
+
+        env.block.time = env.block.time.plus_seconds(ROYALTY_SHARE_INCREASE_INTERVAL);
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("creator", &[]),
+            update(Decimal::percent(7)),
+        )
+        .unwrap();
+
+        // the external link can be removed
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateCollectionInfo {
+                description: None,
+                image: None,
+                external_link: None,
+                royalty_info: None,
+                clear_external_link: true,
+            },
+        )
+        .unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::CollectionInfo {}).unwrap();
+        let value: CollectionInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(None, value.external_link);
+    }
+
+    #[test]
+    fn freeze_collection_info() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), None);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("minter", &[]),
+            ExecuteMsg::FreezeCollectionInfo {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::FreezeCollectionInfo {},
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateCollectionInfo {
+                description: Some(String::from("Passage Apes")),
+                image: None,
+                external_link: None,
+                royalty_info: None,
+                clear_external_link: false,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CollectionInfoFrozen {}));
+    }
+
+    #[test]
+    fn mint_with_metadata() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), None);
+
+        let metadata = Metadata {
+            name: Some(String::from("Monkey #1")),
+            description: Some(String::from("The first monkey")),
+            image: Some(String::from("https://example.com/1.png")),
+            attributes: vec![Trait {
+                display_type: None,
+                trait_type: String::from("fur"),
+                value: String::from("golden"),
+            }],
+            animation_url: None,
+            external_url: Some(String::from("https://example.com/1.html")),
+        };
+        let mint = |token_id: &str, metadata: Metadata| {
+            ExecuteMsg::Mint(MintMsg {
+                token_id: String::from(token_id),
+                owner: String::from("owner"),
+                token_uri: None,
+                extension: Some(metadata),
+            })
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("minter", &[]),
+            mint(
+                "1",
+                Metadata {
+                    animation_url: Some(String::from("not a url")),
+                    ..metadata.clone()
+                },
+            ),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Parse(_)));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("minter", &[]),
+            mint("1", metadata.clone()),
+        )
+        .unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::NftInfo {
+                token_id: String::from("1"),
+            },
+        )
+        .unwrap();
+        let value: NftInfoResponse<Extension> = from_binary(&res).unwrap();
+        assert_eq!(Some(metadata), value.extension);
+    }
+
+    fn setup_sale_contract(deps: DepsMut, start_time: Timestamp) {
+        let msg = InstantiateMsg {
+            name: String::from("collection0"),
+            symbol: String::from("BOBO"),
+            minter: String::from("minter"),
+            collection_info: CollectionInfo {
+                creator: String::from("creator"),
+                description: String::from("Passage Monkeys"),
+                image: String::from("https://example.com/image.png"),
+                external_link: None,
+                royalty_info: None,
+            },
+            sale: Some(SaleConfig {
+                price: coin(100, NATIVE_DENOM),
+                max_supply: 3,
+                per_address_limit: 2,
+                start_time,
+                end_time: Some(start_time.plus_seconds(3_600)),
+                base_token_uri: String::from("ipfs://monkeys/"),
+                platform_fee: Some(PlatformFeeResponse {
+                    address: String::from("platform"),
+                    share: Decimal::percent(5),
+                }),
+            }),
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn public_sale() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_sale_contract(deps.as_mut(), env.block.time.plus_seconds(60));
+
+        let mint = |deps: DepsMut, env: Env, buyer: &str, funds: &[Coin]| {
+            execute(
+                deps,
+                env,
+                mock_info(buyer, funds),
+                ExecuteMsg::MintPublic {},
+            )
+        };
+
+        let err = mint(
+            deps.as_mut(),
+            env.clone(),
+            "alice",
+            &coins(100, NATIVE_DENOM),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SaleNotStarted { .. }));
+
+        env.block.time = env.block.time.plus_seconds(60);
+        let err = mint(
+            deps.as_mut(),
+            env.clone(),
+            "alice",
+            &coins(50, NATIVE_DENOM),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::IncorrectPayment { .. }));
+        let err = mint(deps.as_mut(), env.clone(), "alice", &[]).unwrap_err();
+        assert!(matches!(err, ContractError::Payment(_)));
+
+        // token ids are auto-incremented
+        mint(
+            deps.as_mut(),
+            env.clone(),
+            "alice",
+            &coins(100, NATIVE_DENOM),
+        )
+        .unwrap();
+        let res = mint(
+            deps.as_mut(),
+            env.clone(),
+            "alice",
+            &coins(100, NATIVE_DENOM),
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&Attribute::new("token_id", "2")));
+        let err = mint(
+            deps.as_mut(),
+            env.clone(),
+            "alice",
+            &coins(100, NATIVE_DENOM),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MintLimitReached { limit: 2 }));
+
+        mint(deps.as_mut(), env.clone(), "bob", &coins(100, NATIVE_DENOM)).unwrap();
+        let err = mint(deps.as_mut(), env.clone(), "bob", &coins(100, NATIVE_DENOM)).unwrap_err();
+        assert!(matches!(err, ContractError::SoldOut {}));
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::NftInfo {
+                token_id: String::from("3"),
+            },
+        )
+        .unwrap();
+        let value: NftInfoResponse<Extension> = from_binary(&res).unwrap();
+        assert_eq!(Some(String::from("ipfs://monkeys/3")), value.token_uri);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::SaleInfo {}).unwrap();
+        let value: SaleInfoResponse = from_binary(&res).unwrap();
+        assert_eq!((3, 0), (value.minted, value.remaining));
+
+        // proceeds go to the creator, minus the platform fee
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Withdraw {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        deps.querier
+            .update_balance(env.contract.address.clone(), coins(300, NATIVE_DENOM));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::Withdraw {},
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: String::from("platform"),
+                    amount: coins(15, NATIVE_DENOM),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: String::from("creator"),
+                    amount: coins(285, NATIVE_DENOM),
+                }),
+            ],
+            res.messages
+        );
+    }
+
+    #[test]
+    fn public_sale_ended() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_sale_contract(deps.as_mut(), env.block.time);
+
+        env.block.time = env.block.time.plus_seconds(3_600);
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("alice", &coins(100, NATIVE_DENOM)),
+            ExecuteMsg::MintPublic {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SaleEnded {}));
+    }
+
+    #[test]
+    fn public_sale_with_minter_mints() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_sale_contract(deps.as_mut(), env.block.time);
+
+        // the minter takes an id ahead of the sale
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("minter", &[]),
+            ExecuteMsg::Mint(MintMsg {
+                token_id: String::from("2"),
+                owner: String::from("minter"),
+                token_uri: None,
+                extension: None,
+            }),
+        )
+        .unwrap();
+
+        let mint = |deps: DepsMut, buyer: &str| {
+            execute(
+                deps,
+                env.clone(),
+                mock_info(buyer, &coins(100, NATIVE_DENOM)),
+                ExecuteMsg::MintPublic {},
+            )
+        };
+        let res = mint(deps.as_mut(), "alice").unwrap();
+        assert!(res.attributes.contains(&Attribute::new("token_id", "1")));
+        let res = mint(deps.as_mut(), "alice").unwrap();
+        assert!(res.attributes.contains(&Attribute::new("token_id", "3")));
+
+        // the minter's mint counts against the max supply
+        let err = mint(deps.as_mut(), "bob").unwrap_err();
+        assert!(matches!(err, ContractError::SoldOut {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("minter", &[]),
+            ExecuteMsg::Mint(MintMsg {
+                token_id: String::from("4"),
+                owner: String::from("minter"),
+                token_uri: None,
+                extension: None,
+            }),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SoldOut {}));
+    }
+
+    fn leaf(address: &str, allocation: u32) -> [u8; 32] {
+        Sha256::new()
+            .chain_update(address.as_bytes())
+            .chain_update(allocation.to_be_bytes())
+            .finalize()
+            .into()
+    }
+
+    fn parent(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        Sha256::new()
+            .chain_update(first)
+            .chain_update(second)
+            .finalize()
+            .into()
+    }
+
+    #[test]
+    fn allowlist_phase() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let start_time = env.block.time.plus_seconds(60);
+        setup_sale_contract(deps.as_mut(), start_time);
+
+        let alice = leaf("alice", 2);
+        let bob = leaf("bob", 1);
+        let carol = leaf("carol", 1);
+        let root = parent(parent(alice, bob), carol);
+
+        let set_allowlist = ExecuteMsg::SetAllowlist {
+            merkle_root: HexBinary::from(root),
+            start_time: env.block.time,
+            end_time: start_time.plus_seconds(60),
+            price: coin(50, NATIVE_DENOM),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            set_allowlist.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // proceeds are withdrawn in the sale denom only
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SetAllowlist {
+                merkle_root: HexBinary::from(root),
+                start_time: env.block.time,
+                end_time: start_time.plus_seconds(60),
+                price: coin(50, "uother"),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAllowlist(_)));
+
+        // the allowlist closes with the sale at the latest
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SetAllowlist {
+                merkle_root: HexBinary::from(root),
+                start_time: env.block.time,
+                end_time: start_time.plus_seconds(3_601),
+                price: coin(50, NATIVE_DENOM),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAllowlist(_)));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            set_allowlist.clone(),
+        )
+        .unwrap();
+
+        // the phase can not be moved once it started
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            set_allowlist,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAllowlist(_)));
+
+        let mint = |deps: DepsMut, env: Env, buyer: &str, proof: &[[u8; 32]], allocation| {
+            execute(
+                deps,
+                env,
+                mock_info(buyer, &coins(50, NATIVE_DENOM)),
+                ExecuteMsg::MintAllowlist {
+                    proof: proof.iter().copied().map(HexBinary::from).collect(),
+                    allocation,
+                },
+            )
+        };
+        let alice_proof = [bob, carol];
+
+        // the allocation is part of the leaf
+        let err = mint(deps.as_mut(), env.clone(), "alice", &alice_proof, 3).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidProof {}));
+        let err = mint(deps.as_mut(), env.clone(), "bob", &alice_proof, 2).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidProof {}));
+
+        mint(deps.as_mut(), env.clone(), "alice", &alice_proof, 2).unwrap();
+        mint(deps.as_mut(), env.clone(), "alice", &alice_proof, 2).unwrap();
+        let err = mint(deps.as_mut(), env.clone(), "alice", &alice_proof, 2).unwrap_err();
+        assert!(matches!(err, ContractError::MintLimitReached { limit: 2 }));
+        mint(
+            deps.as_mut(),
+            env.clone(),
+            "carol",
+            &[parent(alice, bob)],
+            1,
+        )
+        .unwrap();
+
+        // the public sale waits for the allowlist phase to end
+        env.block.time = start_time;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("dave", &coins(100, NATIVE_DENOM)),
+            ExecuteMsg::MintPublic {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SaleNotStarted { .. }));
+
+        env.block.time = start_time.plus_seconds(60);
+        let err = mint(deps.as_mut(), env.clone(), "bob", &[alice, carol], 1).unwrap_err();
+        assert!(matches!(err, ContractError::AllowlistNotActive {}));
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("dave", &coins(100, NATIVE_DENOM)),
+            ExecuteMsg::MintPublic {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SoldOut {}));
+    }
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, Decimal, StdError, Timestamp};
 use cw721_base::ContractError as Cw721ContractError;
 use cw_utils::PaymentError;
 use thiserror::Error;
@@ -9,6 +9,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Base(#[from] Cw721ContractError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -30,20 +33,51 @@ pub enum ContractError {
     #[error("Description too long")]
     DescriptionTooLong {},
 
+    #[error("Collection info is frozen")]
+    CollectionInfoFrozen {},
+
+    #[error("Royalty share can increase by at most {max}")]
+    RoyaltyShareIncreaseTooLarge { max: Decimal },
+
+    #[error("Royalty share cannot increase again before {time}")]
+    RoyaltyShareIncreaseTooSoon { time: Timestamp },
+
+    #[error("Invalid sale: {0}")]
+    InvalidSale(String),
+
+    #[error("No sale is configured")]
+    NoSale {},
+
+    #[error("Sale starts at {start_time}")]
+    SaleNotStarted { start_time: Timestamp },
+
+    #[error("Sale has ended")]
+    SaleEnded {},
+
+    #[error("Sold out")]
+    SoldOut {},
+
+    #[error("Cannot mint more than {limit} tokens per address")]
+    MintLimitReached { limit: u32 },
+
+    #[error("Incorrect payment, expected {expected}")]
+    IncorrectPayment { expected: Coin },
+
+    #[error("Invalid allowlist: {0}")]
+    InvalidAllowlist(String),
+
+    #[error("Allowlist phase is not active")]
+    AllowlistNotActive {},
+
+    #[error("Invalid merkle proof")]
+    InvalidProof {},
+
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw {},
+
     #[error("{0}")]
     Payment(#[from] PaymentError),
 
     #[error("{0}")]
     Parse(#[from] ParseError),
 }
-
-impl From<ContractError> for Cw721ContractError {
-    fn from(err: ContractError) -> Cw721ContractError {
-        match err {
-            ContractError::Unauthorized {} => Cw721ContractError::Unauthorized {},
-            ContractError::Claimed {} => Cw721ContractError::Claimed {},
-            ContractError::Expired {} => Cw721ContractError::Expired {},
-            _ => unreachable!("cannot convert {:?} to Cw721ContractError", err),
-        }
-    }
-}
@@ -1,6 +1,10 @@
-use crate::{state::CollectionInfo, ContractError};
-use cosmwasm_std::{Decimal, Empty};
-use cw721_base::msg::QueryMsg as Cw721QueryMsg;
+use crate::{
+    state::{AllowlistConfig, CollectionInfo, Extension, SaleConfig},
+    ContractError,
+};
+use cosmwasm_std::{Binary, Coin, Decimal, Empty, HexBinary, Timestamp, Uint128};
+use cw721_base::msg::{ExecuteMsg as Cw721ExecuteMsg, MintMsg, QueryMsg as Cw721QueryMsg};
+use cw721_spec::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -10,6 +14,7 @@ pub struct InstantiateMsg {
     pub symbol: String,
     pub minter: String,
     pub collection_info: CollectionInfo<RoyaltyInfoResponse>,
+    pub sale: Option<SaleConfig<PlatformFeeResponse>>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -28,7 +33,110 @@ impl RoyaltyInfoResponse {
     }
 }
 
-pub type ExecuteMsg = cw721_base::ExecuteMsg<Empty, Empty>;
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    TransferNft {
+        recipient: String,
+        token_id: String,
+    },
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+    Approve {
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    Revoke {
+        spender: String,
+        token_id: String,
+    },
+    ApproveAll {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+    RevokeAll {
+        operator: String,
+    },
+    Mint(MintMsg<Extension>),
+    Burn {
+        token_id: String,
+    },
+    /// Update the collection info, creator only. Unset fields are left unchanged
+    UpdateCollectionInfo {
+        description: Option<String>,
+        image: Option<String>,
+        external_link: Option<String>,
+        royalty_info: Option<RoyaltyInfoResponse>,
+        /// Remove the external link, `external_link` is then ignored
+        #[serde(default)]
+        clear_external_link: bool,
+    },
+    /// Make the collection info immutable, creator only
+    FreezeCollectionInfo {},
+    /// Set the allowlist phase, creator only
+    SetAllowlist {
+        merkle_root: HexBinary,
+        start_time: Timestamp,
+        end_time: Timestamp,
+        price: Coin,
+    },
+    /// Buy the next token of the sale during the allowlist phase. The proof shows that
+    /// `(sender, allocation)` is a leaf of the allowlist merkle tree
+    MintAllowlist {
+        proof: Vec<HexBinary>,
+        allocation: u32,
+    },
+    /// Buy the next token of the sale
+    MintPublic {},
+    /// Send the sale proceeds to the creator, minus the platform fee
+    Withdraw {},
+}
+
+impl From<ExecuteMsg> for Cw721ExecuteMsg<Extension, Empty> {
+    fn from(msg: ExecuteMsg) -> Cw721ExecuteMsg<Extension, Empty> {
+        match msg {
+            ExecuteMsg::TransferNft {
+                recipient,
+                token_id,
+            } => Cw721ExecuteMsg::TransferNft {
+                recipient,
+                token_id,
+            },
+            ExecuteMsg::SendNft {
+                contract,
+                token_id,
+                msg,
+            } => Cw721ExecuteMsg::SendNft {
+                contract,
+                token_id,
+                msg,
+            },
+            ExecuteMsg::Approve {
+                spender,
+                token_id,
+                expires,
+            } => Cw721ExecuteMsg::Approve {
+                spender,
+                token_id,
+                expires,
+            },
+            ExecuteMsg::Revoke { spender, token_id } => {
+                Cw721ExecuteMsg::Revoke { spender, token_id }
+            }
+            ExecuteMsg::ApproveAll { operator, expires } => {
+                Cw721ExecuteMsg::ApproveAll { operator, expires }
+            }
+            ExecuteMsg::RevokeAll { operator } => Cw721ExecuteMsg::RevokeAll { operator },
+            ExecuteMsg::Mint(msg) => Cw721ExecuteMsg::Mint(msg),
+            ExecuteMsg::Burn { token_id } => Cw721ExecuteMsg::Burn { token_id },
+            _ => unreachable!("cannot convert {:?} to Cw721ExecuteMsg", msg),
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -72,6 +180,15 @@ pub enum QueryMsg {
     },
     Minter {},
     CollectionInfo {},
+    /// CW2981: returns the royalty owed on a sale of the token at `sale_price`
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128,
+    },
+    /// CW2981: returns whether the collection pays royalties
+    CheckRoyalties {},
+    SaleInfo {},
+    AllowlistInfo {},
 }
 
 impl From<QueryMsg> for Cw721QueryMsg<Empty> {
@@ -147,3 +264,37 @@ pub struct CollectionInfoResponse {
     pub external_link: Option<String>,
     pub royalty_info: Option<RoyaltyInfoResponse>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltiesInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckRoyaltiesResponse {
+    pub royalty_payments: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlatformFeeResponse {
+    pub address: String,
+    pub share: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SaleInfoResponse {
+    pub price: Coin,
+    pub max_supply: u32,
+    pub per_address_limit: u32,
+    pub start_time: Timestamp,
+    pub end_time: Option<Timestamp>,
+    pub platform_fee: Option<PlatformFeeResponse>,
+    pub minted: u32,
+    pub remaining: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowlistInfoResponse {
+    pub allowlist: Option<AllowlistConfig>,
+}
@@ -1,5 +1,5 @@
-use cosmwasm_std::{Addr, Decimal};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Coin, Decimal, HexBinary, Timestamp};
+use cw_storage_plus::{Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -18,4 +18,74 @@ pub struct RoyaltyInfo {
     pub share: Decimal,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Trait {
+    pub display_type: Option<String>,
+    pub trait_type: String,
+    pub value: String,
+}
+
+/// On-chain metadata of a token, following the OpenSea metadata standard
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Metadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    #[serde(default)]
+    pub attributes: Vec<Trait>,
+    pub animation_url: Option<String>,
+    pub external_url: Option<String>,
+}
+
+pub type Extension = Option<Metadata>;
+
 pub const COLLECTION_INFO: Item<CollectionInfo<RoyaltyInfo>> = Item::new("collection_info");
+
+pub const FROZEN_COLLECTION_INFO: Item<bool> = Item::new("frozen_collection_info");
+
+/// Last time the royalty share was increased
+pub const ROYALTY_UPDATED_AT: Item<Timestamp> = Item::new("royalty_updated_at");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SaleConfig<T> {
+    pub price: Coin,
+    pub max_supply: u32,
+    pub per_address_limit: u32,
+    pub start_time: Timestamp,
+    pub end_time: Option<Timestamp>,
+    /// Sold tokens get `{base_token_uri}/{token_id}` as token uri
+    pub base_token_uri: String,
+    pub platform_fee: Option<T>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlatformFee {
+    pub address: Addr,
+    pub share: Decimal,
+}
+
+pub const SALE: Item<SaleConfig<PlatformFee>> = Item::new("sale");
+
+/// Number of tokens minted by the sale or the minter, all count against the max supply
+pub const SALE_MINTED: Item<u32> = Item::new("sale_minted");
+
+/// Token id the sale mints next, ids the minter already took are skipped
+pub const SALE_NEXT_TOKEN_ID: Item<u32> = Item::new("sale_next_token_id");
+
+/// Number of tokens bought by each address
+pub const SALE_MINTS: Map<&Addr, u32> = Map::new("sale_mints");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowlistConfig {
+    /// Root of a merkle tree of `(address, allocation)` leaves
+    pub merkle_root: HexBinary,
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+    pub price: Coin,
+}
+
+/// The presale phase, the public sale starts once it ends
+pub const ALLOWLIST: Item<AllowlistConfig> = Item::new("allowlist");
+
+/// Number of tokens bought by each address during the allowlist phase
+pub const ALLOWLIST_MINTS: Map<&Addr, u32> = Map::new("allowlist_mints");
@@ -3,7 +3,10 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use native_vault::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use native_vault::msg::{
+    BasketResponse, ConfigResponse, ExecuteMsg, HistoryResponse, InstantiateMsg, QueryMsg,
+    SimulateBondResponse, SimulateUnbondResponse, SudoMsg, VaultHookMsg, VaultInfoResponse,
+};
 use native_vault::state::Config;
 
 fn main() {
@@ -15,6 +18,13 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
     export_schema(&schema_for!(Config), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(VaultHookMsg), &out_dir);
+    export_schema(&schema_for!(SimulateBondResponse), &out_dir);
+    export_schema(&schema_for!(SimulateUnbondResponse), &out_dir);
+    export_schema(&schema_for!(HistoryResponse), &out_dir);
+    export_schema(&schema_for!(VaultInfoResponse), &out_dir);
+    export_schema(&schema_for!(BasketResponse), &out_dir);
 }
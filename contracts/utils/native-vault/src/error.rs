@@ -1,4 +1,6 @@
+use crate::msg::VaultLimit;
 use cosmwasm_std::StdError;
+use cw_controllers::HookError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -17,4 +19,32 @@ pub enum ContractError {
 
     #[error("Vault already exists: {0}")]
     VaultAlreadyExists(String),
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Amount is below the minimum")]
+    BelowMinimum {},
+
+    #[error("Minter cap exceeded")]
+    CapExceeded {},
+
+    #[error("Invalid basket: {0}")]
+    InvalidBasket(String),
+
+    #[error("{0}")]
+    Hook(#[from] HookError),
+
+    #[error("Too many hooks: at most {0} are allowed")]
+    TooManyHooks(usize),
+}
+
+impl From<VaultLimit> for ContractError {
+    fn from(limit: VaultLimit) -> ContractError {
+        match limit {
+            VaultLimit::Paused => ContractError::Paused {},
+            VaultLimit::Minimum => ContractError::BelowMinimum {},
+            VaultLimit::Cap => ContractError::CapExceeded {},
+        }
+    }
 }
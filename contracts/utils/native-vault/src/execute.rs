@@ -1,11 +1,20 @@
 use crate::error::ContractError;
-use crate::msg::ExecuteMsg;
-use crate::state::{CONFIG, VAULT_ADDRESSES};
+use crate::helpers::{
+    calculate_bond, calculate_unbond, only_admin, prepare_bond_hooks, prepare_hooks,
+    query_receipt_balance, query_receipt_supply, record_action,
+};
+use crate::msg::{ExecuteMsg, VaultHookMsg};
+use crate::state::{
+    ActionKind, Basket, BasketComponent, VaultDecimals, BASKET, CONFIG, DUST, HOOKS, MAX_HOOKS,
+    VAULT_ADDRESSES, VAULT_DECIMALS,
+};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, BankMsg, Coin, CosmosMsg, DepsMut, Env, MessageInfo, Response, Uint128, WasmMsg,
+    to_binary, BankMsg, Coin, CosmosMsg, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    Uint128, WasmMsg,
 };
+use cw20::{Cw20QueryMsg, TokenInfoResponse};
 use cw_utils::one_coin;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -18,10 +27,23 @@ pub fn execute(
     match msg {
         ExecuteMsg::Bond { denom, amount } => execute_bond(deps, env, info, denom, amount),
         ExecuteMsg::Unbond { denom, amount } => execute_unbond(deps, env, info, denom, amount),
-        ExecuteMsg::AddVault { denom, address } => {
-            execute_add_vault(deps, env, info, denom, address)
-        }
-        ExecuteMsg::UpdateConfig { admin } => execute_update_config(deps, env, info, admin),
+        ExecuteMsg::AddVault {
+            denom,
+            address,
+            native_decimals,
+        } => execute_add_vault(deps, env, info, denom, address, native_decimals),
+        ExecuteMsg::UpdateConfig {
+            admin,
+            history_limit,
+        } => execute_update_config(deps, env, info, admin, history_limit),
+        ExecuteMsg::AddHook { addr } => execute_add_hook(deps, env, info, addr),
+        ExecuteMsg::RemoveHook { addr } => execute_remove_hook(deps, env, info, addr),
+        ExecuteMsg::SetBasket {
+            receipt,
+            components,
+        } => execute_set_basket(deps, env, info, receipt, components),
+        ExecuteMsg::BondBasket {} => execute_bond_basket(deps, env, info),
+        ExecuteMsg::UnbondBasket { amount } => execute_unbond_basket(deps, env, info, amount),
     }
 }
 
@@ -29,14 +51,21 @@ pub fn execute(
 pub fn execute_update_config(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     admin: Option<String>,
+    history_limit: Option<u64>,
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
+    if history_limit.is_some() {
+        only_admin(&info.sender, &config)?;
+    }
 
     if let Some(admin) = admin {
         config.admin = deps.api.addr_validate(&admin)?;
     }
+    if let Some(history_limit) = history_limit {
+        config.history_limit = Some(history_limit).filter(|limit| *limit > 0);
+    }
 
     CONFIG.save(deps.storage, &config)?;
     Ok(Response::default())
@@ -45,54 +74,98 @@ pub fn execute_update_config(
 /// Bond native tokens to the contract
 pub fn execute_bond(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     denom: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    // Get the receipt amount, the same way SimulateBond does
+    let calculation = calculate_bond(deps.as_ref(), &denom, amount)?;
+    if let Some(limit) = calculation.limit {
+        return Err(limit.into());
+    }
+
     let _coin = one_coin(&info)
         .map_err(|e| ContractError::PaymentError(format!("Expected one coin, got: {}", e)))?;
 
-    // Get the address of the vault for this denom
-    let vault_address = VAULT_ADDRESSES.load(deps.storage, denom).map_err(
-        // Vault doesn't exist error
-        |e| ContractError::VaultDoesNotExist(e.to_string()),
+    // Notify the hook subscribers of the new receipt balance
+    let old = query_receipt_balance(&deps.querier, &calculation.vault_address, &info.sender)?;
+    let new = old
+        .checked_add(calculation.receipt_amount)
+        .map_err(StdError::from)?;
+    let hooks = prepare_bond_hooks(deps.storage, &info.sender, &denom, old, new)?;
+
+    // Keep track of the native amount that was not converted into receipts
+    if !calculation.dust.is_zero() {
+        DUST.update(deps.storage, denom.clone(), |dust| -> StdResult<_> {
+            Ok(dust.unwrap_or_default().checked_add(calculation.dust)?)
+        })?;
+    }
+
+    let action_id = record_action(
+        deps.storage,
+        &env,
+        &info.sender,
+        ActionKind::Bond,
+        &denom,
+        amount,
+        calculation.receipt_amount,
     )?;
 
     // Mint the tokens
     let mint_msg = cw20_base::msg::ExecuteMsg::Mint {
         recipient: info.sender.to_string(),
-        amount: amount,
+        amount: calculation.receipt_amount,
     };
     let mint_cosmos_msg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: vault_address.to_string(),
+        contract_addr: calculation.vault_address.to_string(),
         msg: to_binary(&mint_msg)?,
         funds: vec![],
     });
 
     Ok(Response::new()
         .add_attribute("action", "bond")
-        .add_message(mint_cosmos_msg))
+        .add_attribute("action_id", action_id.to_string())
+        .add_message(mint_cosmos_msg)
+        .add_submessages(hooks))
 }
 
 /// Unbond native tokens from the contract
 pub fn execute_unbond(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     denom: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
-    // Get the address of the vault for this denom
-    let vault_address = VAULT_ADDRESSES.load(deps.storage, denom.clone()).map_err(
-        // Vault doesn't exist error
-        |e| ContractError::VaultDoesNotExist(e.to_string()),
+    // Get the native amount, the same way SimulateUnbond does
+    let calculation = calculate_unbond(deps.as_ref(), &denom, amount)?;
+    if let Some(limit) = calculation.limit {
+        return Err(limit.into());
+    }
+    let vault_address = calculation.vault_address;
+
+    // Notify the hook subscribers of the new receipt balance
+    let old = query_receipt_balance(&deps.querier, &vault_address, &info.sender)?;
+    let new = old
+        .checked_sub(calculation.burn_amount)
+        .map_err(StdError::from)?;
+    let hooks = prepare_bond_hooks(deps.storage, &info.sender, &denom, old, new)?;
+
+    let action_id = record_action(
+        deps.storage,
+        &env,
+        &info.sender,
+        ActionKind::Unbond,
+        &denom,
+        calculation.native_amount,
+        calculation.burn_amount,
     )?;
 
     // Burn the tokens
     let burn_from_msg = cw20_base::msg::ExecuteMsg::BurnFrom {
         owner: info.sender.to_string(),
-        amount,
+        amount: calculation.burn_amount,
     };
     let burn_cosmos_msg = WasmMsg::Execute {
         contract_addr: vault_address.to_string(),
@@ -104,15 +177,17 @@ pub fn execute_unbond(
     let send_cosmos_msg = BankMsg::Send {
         to_address: info.sender.to_string(),
         amount: vec![Coin {
-            denom: denom.clone(),
-            amount: amount.clone(),
+            denom,
+            amount: calculation.native_amount,
         }],
     };
 
     Ok(Response::new()
         .add_attribute("action", "unbond")
+        .add_attribute("action_id", action_id.to_string())
         .add_message(burn_cosmos_msg)
-        .add_message(send_cosmos_msg))
+        .add_message(send_cosmos_msg)
+        .add_submessages(hooks))
 }
 
 /// Add a new vault to the contract
@@ -122,6 +197,7 @@ pub fn execute_add_vault(
     _info: MessageInfo,
     denom: String,
     address: String,
+    native_decimals: u8,
 ) -> Result<Response, ContractError> {
     // Add the vault to the list of vaults
     VAULT_ADDRESSES.save(
@@ -130,6 +206,19 @@ pub fn execute_add_vault(
         &deps.api.addr_validate(&address)?,
     )?;
 
+    // Record the decimals of both sides to scale bond and unbond
+    let token_info: TokenInfoResponse = deps
+        .querier
+        .query_wasm_smart(&address, &Cw20QueryMsg::TokenInfo {})?;
+    VAULT_DECIMALS.save(
+        deps.storage,
+        denom.clone(),
+        &VaultDecimals {
+            native: native_decimals,
+            receipt: token_info.decimals,
+        },
+    )?;
+
     // Make sure that we are the minter by minting one token and burning it
     let mint_msg = cw20_base::msg::ExecuteMsg::Mint {
         recipient: env.contract.address.to_string(),
@@ -155,3 +244,276 @@ pub fn execute_add_vault(
         .add_message(mint_cosmos_msg)
         .add_message(burn_cosmos_msg))
 }
+
+/// Subscribe a contract to bond and unbond notifications
+pub fn execute_add_hook(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    only_admin(&info.sender, &config)?;
+
+    // Keep the gas of bond and unbond bounded
+    let hooks = HOOKS.query_hooks(deps.as_ref())?.hooks;
+    if hooks.len() >= MAX_HOOKS {
+        return Err(ContractError::TooManyHooks(MAX_HOOKS));
+    }
+
+    let hook = deps.api.addr_validate(&addr)?;
+    HOOKS.add_hook(deps.storage, hook)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_hook")
+        .add_attribute("hook", addr))
+}
+
+/// Unsubscribe a contract from bond and unbond notifications
+pub fn execute_remove_hook(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    only_admin(&info.sender, &config)?;
+
+    let hook = deps.api.addr_validate(&addr)?;
+    HOOKS.remove_hook(deps.storage, hook)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_hook")
+        .add_attribute("hook", addr))
+}
+
+/// Set the receipt and the composition of the basket
+pub fn execute_set_basket(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    receipt: String,
+    components: Vec<BasketComponent>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    only_admin(&info.sender, &config)?;
+
+    if components.is_empty() {
+        return Err(ContractError::InvalidBasket(String::from(
+            "at least one component is required",
+        )));
+    }
+    for (i, component) in components.iter().enumerate() {
+        if component.weight.is_zero() {
+            return Err(ContractError::InvalidBasket(format!(
+                "zero weight for {}",
+                component.denom
+            )));
+        }
+        if components[..i].iter().any(|c| c.denom == component.denom) {
+            return Err(ContractError::InvalidBasket(format!(
+                "duplicate denom {}",
+                component.denom
+            )));
+        }
+    }
+
+    // Receipts are redeemed at the weights they were minted with, so the basket and its
+    // receipt can only change while no receipt is outstanding
+    let receipt_address = deps.api.addr_validate(&receipt)?;
+    let previous = BASKET.may_load(deps.storage)?.map(|basket| basket.receipt);
+    for address in previous.iter().chain([&receipt_address]) {
+        if !query_receipt_supply(&deps.querier, address)?.is_zero() {
+            return Err(ContractError::InvalidBasket(format!(
+                "receipts of {} are outstanding",
+                address
+            )));
+        }
+    }
+
+    let basket = Basket {
+        receipt: receipt_address,
+        components,
+    };
+    BASKET.save(deps.storage, &basket)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_basket")
+        .add_attribute("receipt", receipt))
+}
+
+/// Bond the basket coins in the weighted ratio and refund the excess
+pub fn execute_bond_basket(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.paused {
+        return Err(ContractError::Paused {});
+    }
+    let basket = BASKET
+        .load(deps.storage)
+        .map_err(|e| ContractError::InvalidBasket(e.to_string()))?;
+
+    // Only the basket denoms are accepted
+    if let Some(coin) = info
+        .funds
+        .iter()
+        .find(|coin| !basket.components.iter().any(|c| c.denom == coin.denom))
+    {
+        return Err(ContractError::PaymentError(format!(
+            "{} is not part of the basket",
+            coin.denom
+        )));
+    }
+
+    // The scarcest component sets the number of receipt units
+    let sent = |denom: &str| {
+        info.funds
+            .iter()
+            .find(|coin| coin.denom == denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default()
+    };
+    let units = basket
+        .components
+        .iter()
+        .map(|c| sent(&c.denom) / c.weight)
+        .min()
+        .unwrap_or_default();
+    if units.is_zero() {
+        return Err(ContractError::BelowMinimum {});
+    }
+
+    // Refund what exceeds the weighted ratio
+    let refund: Vec<Coin> = basket
+        .components
+        .iter()
+        .map(|c| Coin {
+            denom: c.denom.clone(),
+            amount: sent(&c.denom) - units * c.weight,
+        })
+        .filter(|coin| !coin.amount.is_zero())
+        .collect();
+
+    // Notify the hook subscribers of the new receipt balance
+    let old = query_receipt_balance(&deps.querier, &basket.receipt, &info.sender)?;
+    let new = old.checked_add(units).map_err(StdError::from)?;
+    let hooks = prepare_hooks(
+        deps.storage,
+        &VaultHookMsg::BasketBondChanged {
+            user: info.sender.to_string(),
+            old,
+            new,
+        },
+    )?;
+
+    for c in &basket.components {
+        record_action(
+            deps.storage,
+            &env,
+            &info.sender,
+            ActionKind::BondBasket,
+            &c.denom,
+            units * c.weight,
+            units,
+        )?;
+    }
+
+    let mint_msg = cw20_base::msg::ExecuteMsg::Mint {
+        recipient: info.sender.to_string(),
+        amount: units,
+    };
+    let mut response = Response::new()
+        .add_attribute("action", "bond_basket")
+        .add_attribute("amount", units)
+        .add_message(WasmMsg::Execute {
+            contract_addr: basket.receipt.to_string(),
+            msg: to_binary(&mint_msg)?,
+            funds: vec![],
+        });
+    if !refund.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: refund,
+        });
+    }
+
+    Ok(response.add_submessages(hooks))
+}
+
+/// Unbond basket receipts and return every component pro rata
+pub fn execute_unbond_basket(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.paused {
+        return Err(ContractError::Paused {});
+    }
+    if amount.is_zero() {
+        return Err(ContractError::BelowMinimum {});
+    }
+    let basket = BASKET
+        .load(deps.storage)
+        .map_err(|e| ContractError::InvalidBasket(e.to_string()))?;
+
+    // Burn the receipts
+    let burn_from_msg = cw20_base::msg::ExecuteMsg::BurnFrom {
+        owner: info.sender.to_string(),
+        amount,
+    };
+
+    // Send every component to the user
+    let native = basket
+        .components
+        .iter()
+        .map(|c| -> StdResult<Coin> {
+            Ok(Coin {
+                denom: c.denom.clone(),
+                amount: amount.checked_mul(c.weight)?,
+            })
+        })
+        .collect::<StdResult<Vec<Coin>>>()?;
+
+    // Notify the hook subscribers of the new receipt balance
+    let old = query_receipt_balance(&deps.querier, &basket.receipt, &info.sender)?;
+    let new = old.checked_sub(amount).map_err(StdError::from)?;
+    let hooks = prepare_hooks(
+        deps.storage,
+        &VaultHookMsg::BasketBondChanged {
+            user: info.sender.to_string(),
+            old,
+            new,
+        },
+    )?;
+
+    for coin in &native {
+        record_action(
+            deps.storage,
+            &env,
+            &info.sender,
+            ActionKind::UnbondBasket,
+            &coin.denom,
+            coin.amount,
+            amount,
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "unbond_basket")
+        .add_attribute("amount", amount)
+        .add_message(WasmMsg::Execute {
+            contract_addr: basket.receipt.to_string(),
+            msg: to_binary(&burn_from_msg)?,
+            funds: vec![],
+        })
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: native,
+        })
+        .add_submessages(hooks))
+}
@@ -1 +1,256 @@
+use crate::error::ContractError;
+use crate::msg::{VaultHookMsg, VaultLimit};
+use crate::state::{
+    history, Action, ActionKind, Config, CONFIG, HOOKS, MAX_PRUNED_PER_ACTION, NEXT_ACTION_ID,
+    VAULT_ADDRESSES, VAULT_DECIMALS,
+};
+use cosmwasm_std::{
+    to_binary, Addr, Deps, Env, Order, QuerierWrapper, StdError, StdResult, Storage, SubMsg,
+    Uint128, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20QueryMsg, MinterResponse, TokenInfoResponse};
 
+/// Outcome of a bond, shared by the execute handler and the simulation
+pub struct BondCalculation {
+    pub vault_address: Addr,
+    pub receipt_amount: Uint128,
+    /// Native amount too small to be represented in receipt units
+    pub dust: Uint128,
+    pub limit: Option<VaultLimit>,
+}
+
+/// Outcome of an unbond, shared by the execute handler and the simulation
+pub struct UnbondCalculation {
+    pub vault_address: Addr,
+    /// Receipt amount actually burned, the remainder stays with the user
+    pub burn_amount: Uint128,
+    pub native_amount: Uint128,
+    pub limit: Option<VaultLimit>,
+}
+
+/// Checks to enforce only the admin
+pub fn only_admin(sender: &Addr, config: &Config) -> Result<(), ContractError> {
+    if *sender != config.admin {
+        return Err(ContractError::Unauthorized(String::from(
+            "only the admin can call this function",
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the cw20 receipt balance of the given owner
+pub fn query_receipt_balance(
+    querier: &QuerierWrapper,
+    vault_address: &Addr,
+    owner: &Addr,
+) -> StdResult<Uint128> {
+    let res: BalanceResponse = querier.query_wasm_smart(
+        vault_address,
+        &Cw20QueryMsg::Balance {
+            address: owner.to_string(),
+        },
+    )?;
+    Ok(res.balance)
+}
+
+/// Returns the total supply of the given cw20 receipt
+pub fn query_receipt_supply(querier: &QuerierWrapper, vault_address: &Addr) -> StdResult<Uint128> {
+    let res: TokenInfoResponse =
+        querier.query_wasm_smart(vault_address, &Cw20QueryMsg::TokenInfo {})?;
+    Ok(res.total_supply)
+}
+
+/// Builds one message per hook subscriber carrying the bond change
+pub fn prepare_bond_hooks(
+    storage: &dyn Storage,
+    user: &Addr,
+    denom: &str,
+    old: Uint128,
+    new: Uint128,
+) -> StdResult<Vec<SubMsg>> {
+    prepare_hooks(
+        storage,
+        &VaultHookMsg::BondChanged {
+            user: user.to_string(),
+            denom: denom.to_string(),
+            old,
+            new,
+        },
+    )
+}
+
+/// Builds one message per hook subscriber carrying the given notification
+pub fn prepare_hooks(storage: &dyn Storage, msg: &VaultHookMsg) -> StdResult<Vec<SubMsg>> {
+    let msg = to_binary(msg)?;
+    HOOKS.prepare_hooks(storage, |hook| {
+        Ok(SubMsg::new(WasmMsg::Execute {
+            contract_addr: hook.to_string(),
+            msg: msg.clone(),
+            funds: vec![],
+        }))
+    })
+}
+
+/// Returns the vault address of the given denom
+pub fn load_vault_address(storage: &dyn Storage, denom: &str) -> Result<Addr, ContractError> {
+    VAULT_ADDRESSES.load(storage, denom.to_string()).map_err(
+        // Vault doesn't exist error
+        |e| ContractError::VaultDoesNotExist(e.to_string()),
+    )
+}
+
+/// Computes the receipt minted for `amount` native tokens and the first limit it would hit
+pub fn calculate_bond(
+    deps: Deps,
+    denom: &str,
+    amount: Uint128,
+) -> Result<BondCalculation, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let vault_address = load_vault_address(deps.storage, denom)?;
+
+    // Scale to the receipt decimals, rounding down in favor of the vault
+    let (receipt_amount, dust) = match decimals_scale(deps.storage, denom)? {
+        Scale::Up(factor) => (
+            amount.checked_mul(factor).map_err(StdError::from)?,
+            Uint128::zero(),
+        ),
+        Scale::Down(factor) => (amount / factor, amount % factor),
+    };
+
+    let limit = if config.paused {
+        Some(VaultLimit::Paused)
+    } else if receipt_amount.is_zero() {
+        Some(VaultLimit::Minimum)
+    } else if exceeds_cap(&deps.querier, &vault_address, receipt_amount)? {
+        Some(VaultLimit::Cap)
+    } else {
+        None
+    };
+
+    Ok(BondCalculation {
+        vault_address,
+        receipt_amount,
+        dust,
+        limit,
+    })
+}
+
+/// Computes the native tokens returned for `amount` receipts and the first limit it would hit
+pub fn calculate_unbond(
+    deps: Deps,
+    denom: &str,
+    amount: Uint128,
+) -> Result<UnbondCalculation, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let vault_address = load_vault_address(deps.storage, denom)?;
+
+    // Scale to the native decimals, only burning the receipts that convert exactly
+    let (burn_amount, native_amount) = match decimals_scale(deps.storage, denom)? {
+        Scale::Up(factor) => {
+            let native_amount = amount / factor;
+            (native_amount * factor, native_amount)
+        }
+        Scale::Down(factor) => (amount, amount.checked_mul(factor).map_err(StdError::from)?),
+    };
+
+    let limit = if config.paused {
+        Some(VaultLimit::Paused)
+    } else if native_amount.is_zero() {
+        Some(VaultLimit::Minimum)
+    } else {
+        None
+    };
+
+    Ok(UnbondCalculation {
+        vault_address,
+        burn_amount,
+        native_amount,
+        limit,
+    })
+}
+
+/// Factor between native units and receipt units
+enum Scale {
+    /// The receipt has at least as many decimals as the native denom
+    Up(Uint128),
+    /// The receipt has fewer decimals than the native denom
+    Down(Uint128),
+}
+
+/// Returns the factor `10^(receipt - native)` of the vault of the given denom
+fn decimals_scale(storage: &dyn Storage, denom: &str) -> StdResult<Scale> {
+    // Vaults added before decimals were recorded are one to one
+    let decimals = match VAULT_DECIMALS.may_load(storage, denom.to_string())? {
+        Some(decimals) => decimals,
+        None => return Ok(Scale::Up(Uint128::one())),
+    };
+
+    let ten = Uint128::new(10);
+    if decimals.receipt >= decimals.native {
+        Ok(Scale::Up(ten.checked_pow(
+            (decimals.receipt - decimals.native) as u32,
+        )?))
+    } else {
+        Ok(Scale::Down(ten.checked_pow(
+            (decimals.native - decimals.receipt) as u32,
+        )?))
+    }
+}
+
+/// Whether minting `amount` more receipts would go over the cw20 minter cap
+fn exceeds_cap(querier: &QuerierWrapper, vault_address: &Addr, amount: Uint128) -> StdResult<bool> {
+    let minter: Option<MinterResponse> =
+        querier.query_wasm_smart(vault_address, &Cw20QueryMsg::Minter {})?;
+    let cap = match minter.and_then(|minter| minter.cap) {
+        Some(cap) => cap,
+        None => return Ok(false),
+    };
+
+    let token_info: TokenInfoResponse =
+        querier.query_wasm_smart(vault_address, &Cw20QueryMsg::TokenInfo {})?;
+    Ok(token_info.total_supply.checked_add(amount)? > cap)
+}
+
+/// Appends an action to the history and prunes a few of the oldest ones above the history limit
+pub fn record_action(
+    storage: &mut dyn Storage,
+    env: &Env,
+    user: &Addr,
+    kind: ActionKind,
+    denom: &str,
+    amount: Uint128,
+    receipt_amount: Uint128,
+) -> StdResult<u64> {
+    let id = NEXT_ACTION_ID.may_load(storage)?.unwrap_or_default();
+    let action = Action {
+        id,
+        user: user.clone(),
+        kind,
+        denom: denom.to_string(),
+        amount,
+        receipt_amount,
+        height: env.block.height,
+        time: env.block.time,
+    };
+    history().save(storage, id, &action)?;
+    NEXT_ACTION_ID.save(storage, &(id + 1))?;
+
+    if let Some(limit) = CONFIG.load(storage)?.history_limit {
+        // Ids are contiguous, so the number of kept actions is known from the oldest one
+        let oldest = history()
+            .keys(storage, None, None, Order::Ascending)
+            .next()
+            .transpose()?
+            .unwrap_or(id);
+        let excess = (id + 1)
+            .saturating_sub(oldest)
+            .saturating_sub(limit)
+            .min(MAX_PRUNED_PER_ACTION);
+        for pruned in oldest..oldest + excess {
+            history().remove(storage, pruned)?;
+        }
+    }
+
+    Ok(id)
+}
@@ -19,7 +19,11 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    let config = Config { admin: info.sender };
+    let config = Config {
+        admin: info.sender,
+        paused: false,
+        history_limit: None,
+    };
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new()
@@ -9,5 +9,6 @@ mod multitest;
 
 pub mod query;
 pub mod state;
+pub mod sudo;
 
 pub use crate::error::ContractError;
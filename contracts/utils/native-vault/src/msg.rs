@@ -1,4 +1,5 @@
-use cosmwasm_std::Uint128;
+use crate::state::{Action, BasketComponent};
+use cosmwasm_std::{Coin, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -12,10 +13,32 @@ pub enum ExecuteMsg {
     Bond { denom: String, amount: Uint128 },
     /// Unbond native tokens from the contract
     Unbond { denom: String, amount: Uint128 },
-    /// Add a vault
-    AddVault { denom: String, address: String },
-    /// Update config
-    UpdateConfig { admin: Option<String> },
+    /// Add a vault, receipts are scaled by `10^(receipt decimals - native_decimals)`
+    AddVault {
+        denom: String,
+        address: String,
+        native_decimals: u8,
+    },
+    /// Update config, a history limit of zero keeps the whole history. Setting the history
+    /// limit is admin only
+    UpdateConfig {
+        admin: Option<String>,
+        history_limit: Option<u64>,
+    },
+    /// Subscribe a contract to bond and unbond notifications, admin only
+    AddHook { addr: String },
+    /// Unsubscribe a contract from bond and unbond notifications, admin only
+    RemoveHook { addr: String },
+    /// Set the receipt and the composition of the basket, admin only. Rejected while basket
+    /// receipts are outstanding
+    SetBasket {
+        receipt: String,
+        components: Vec<BasketComponent>,
+    },
+    /// Bond the basket coins in the weighted ratio, the excess is refunded
+    BondBasket {},
+    /// Unbond basket receipts for every component pro rata
+    UnbondBasket { amount: Uint128 },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -29,6 +52,58 @@ pub enum QueryMsg {
     VaultAddress { denom: String },
     /// Returns all supported denoms
     DenomList {},
+    /// Returns the hook subscribers
+    Hooks {},
+    /// Returns the native coins backing one basket receipt unit
+    BasketComposition {},
+    /// Returns the decimals and the dust of the vault of the given denom
+    VaultInfo { denom: String },
+    /// Returns the receipt minted when bonding `amount` of `denom`
+    SimulateBond { denom: String, amount: Uint128 },
+    /// Returns the native tokens returned when unbonding `amount` of `denom`
+    SimulateUnbond { denom: String, amount: Uint128 },
+    /// Returns the actions of the given owner
+    UserHistory {
+        owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the actions on the given denom
+    DenomHistory {
+        denom: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Halt or resume bond and unbond
+    ForcePause { paused: bool },
+    /// Replace the admin
+    SetAdmin { admin: String },
+    /// Unlink the cw20 vault of the given denom
+    RemoveVault { denom: String },
+}
+
+/// Message sent to every hook subscriber on bond and unbond
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultHookMsg {
+    /// The receipt balance of `user` for `denom` changed from `old` to `new`
+    BondChanged {
+        user: String,
+        denom: String,
+        old: Uint128,
+        new: Uint128,
+    },
+    /// The basket receipt balance of `user` changed from `old` to `new`
+    BasketBondChanged {
+        user: String,
+        old: Uint128,
+        new: Uint128,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -46,7 +121,55 @@ pub struct VaultAddressResponse {
     pub address: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VaultInfoResponse {
+    pub address: String,
+    pub native_decimals: u8,
+    pub receipt_decimals: u8,
+    pub dust: Uint128,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct DenomResponse {
     pub denoms: Vec<String>,
 }
+
+/// Limit that makes a bond or unbond fail
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultLimit {
+    /// The contract is paused
+    Paused,
+    /// The amount is below the minimum
+    Minimum,
+    /// The receipt cw20 minter cap would be exceeded
+    Cap,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateBondResponse {
+    pub receipt_amount: Uint128,
+    /// Native amount kept by the vault because of the decimals difference
+    pub dust: Uint128,
+    pub limit: Option<VaultLimit>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateUnbondResponse {
+    /// Receipt amount burned, the remainder is left to the user
+    pub burn_amount: Uint128,
+    pub native_amount: Uint128,
+    pub limit: Option<VaultLimit>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HistoryResponse {
+    pub actions: Vec<Action>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BasketResponse {
+    pub receipt: String,
+    /// Native coins backing one receipt unit
+    pub components: Vec<Coin>,
+}
@@ -1,11 +1,19 @@
 #![cfg(test)]
 use crate::error::ContractError;
-use crate::msg::DenomResponse;
-use cosmwasm_std::{coins, Addr, Coin, Empty, Uint128};
-use cw20_base;
+use crate::msg::{
+    BasketResponse, DenomResponse, HistoryResponse, SimulateBondResponse, SimulateUnbondResponse,
+    SudoMsg, VaultHookMsg, VaultInfoResponse, VaultLimit,
+};
+use crate::state::{ActionKind, BasketComponent, Config};
+use cosmwasm_std::{
+    coins, to_binary, Addr, Binary, Coin, Deps, DepsMut, Empty, Env, Event, MessageInfo, Response,
+    StdResult, Uint128,
+};
+use cw_controllers::HooksResponse;
 use cw_multi_test::{
     App, AppBuilder, BankSudo, Contract, ContractWrapper, Executor, SudoMsg as CwSudoMsg,
 };
+use cw_storage_plus::Item;
 
 const NATIVE_DENOM: &str = "ucosm";
 const UUSD_DENOM: &str = "uusd";
@@ -43,7 +51,8 @@ pub fn contract_native_vault() -> Box<dyn Contract<Empty>> {
         crate::execute::execute,
         crate::instantiate::instantiate,
         crate::query::query,
-    );
+    )
+    .with_sudo(crate::sudo::sudo);
     Box::new(contract)
 }
 
@@ -56,6 +65,26 @@ pub fn contract_cw20_base() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+/// Last message received by the hook receiver
+const LAST_HOOK: Item<VaultHookMsg> = Item::new("last_hook");
+
+// Records the last hook message it received
+pub fn contract_hook_receiver() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |deps: DepsMut, _env: Env, _info: MessageInfo, msg: VaultHookMsg| -> StdResult<Response> {
+            LAST_HOOK.save(deps.storage, &msg)?;
+            Ok(Response::default())
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::default())
+        },
+        |deps: Deps, _env: Env, _msg: Empty| -> StdResult<Binary> {
+            to_binary(&LAST_HOOK.load(deps.storage)?)
+        },
+    );
+    Box::new(contract)
+}
+
 // Instantiates all needed contracts for testing
 fn setup_contracts(router: &mut App, deployer: &Addr) -> Result<(Addr, Addr, Addr), ContractError> {
     let _block_time = router.block_info().time;
@@ -129,6 +158,7 @@ fn setup_contracts(router: &mut App, deployer: &Addr) -> Result<(Addr, Addr, Add
     let msg = crate::msg::ExecuteMsg::AddVault {
         denom: "ucosm".to_string(),
         address: cw20_uusd.clone().to_string(),
+        native_decimals: 6,
     };
     router
         .execute_contract(deployer.clone(), native_vault.clone(), &msg, &[])
@@ -138,6 +168,7 @@ fn setup_contracts(router: &mut App, deployer: &Addr) -> Result<(Addr, Addr, Add
     let msg = crate::msg::ExecuteMsg::AddVault {
         denom: "uusd".to_string(),
         address: cw20_uusd.clone().to_string(),
+        native_decimals: 6,
     };
     router
         .execute_contract(deployer.clone(), native_vault.clone(), &msg, &[])
@@ -198,3 +229,890 @@ fn test_create_vault() {
         .unwrap();
     assert_eq!(res.denoms.len(), 2);
 }
+
+#[test]
+fn test_hooks() {
+    let mut router = custom_mock_app();
+    let (deployer, rob, _steve) = setup_accounts(&mut router).unwrap();
+    let (native_vault, _cw20_ucosm, cw20_uusd) = setup_contracts(&mut router, &deployer).unwrap();
+
+    // Instantiate the hook receiver
+    let receiver_code_id = router.store_code(contract_hook_receiver());
+    let receiver = router
+        .instantiate_contract(
+            receiver_code_id,
+            deployer.clone(),
+            &Empty {},
+            &[],
+            "hook",
+            None,
+        )
+        .unwrap();
+
+    // Only the admin can add hooks
+    let msg = crate::msg::ExecuteMsg::AddHook {
+        addr: receiver.to_string(),
+    };
+    let err = router
+        .execute_contract(rob.clone(), native_vault.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized(_)
+    ));
+    router
+        .execute_contract(deployer.clone(), native_vault.clone(), &msg, &[])
+        .unwrap();
+
+    let res: HooksResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &crate::msg::QueryMsg::Hooks {})
+        .unwrap();
+    assert_eq!(res.hooks, vec![receiver.to_string()]);
+
+    // Bonding notifies the receiver
+    let msg = crate::msg::ExecuteMsg::Bond {
+        denom: UUSD_DENOM.to_string(),
+        amount: Uint128::new(1_000),
+    };
+    router
+        .execute_contract(
+            rob.clone(),
+            native_vault.clone(),
+            &msg,
+            &coins(1_000, UUSD_DENOM),
+        )
+        .unwrap();
+    let hook: VaultHookMsg = router
+        .wrap()
+        .query_wasm_smart(receiver.clone(), &Empty {})
+        .unwrap();
+    assert_eq!(
+        hook,
+        VaultHookMsg::BondChanged {
+            user: rob.to_string(),
+            denom: UUSD_DENOM.to_string(),
+            old: Uint128::zero(),
+            new: Uint128::new(1_000),
+        }
+    );
+
+    // Unbonding notifies the receiver
+    let msg = cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+        spender: native_vault.to_string(),
+        amount: Uint128::new(400),
+        expires: None,
+    };
+    router
+        .execute_contract(rob.clone(), cw20_uusd, &msg, &[])
+        .unwrap();
+    let msg = crate::msg::ExecuteMsg::Unbond {
+        denom: UUSD_DENOM.to_string(),
+        amount: Uint128::new(400),
+    };
+    router
+        .execute_contract(rob.clone(), native_vault.clone(), &msg, &[])
+        .unwrap();
+    let hook: VaultHookMsg = router
+        .wrap()
+        .query_wasm_smart(receiver.clone(), &Empty {})
+        .unwrap();
+    assert_eq!(
+        hook,
+        VaultHookMsg::BondChanged {
+            user: rob.to_string(),
+            denom: UUSD_DENOM.to_string(),
+            old: Uint128::new(1_000),
+            new: Uint128::new(600),
+        }
+    );
+
+    // Removed hooks are no longer listed
+    let msg = crate::msg::ExecuteMsg::RemoveHook {
+        addr: receiver.to_string(),
+    };
+    router
+        .execute_contract(deployer, native_vault.clone(), &msg, &[])
+        .unwrap();
+    let res: HooksResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault, &crate::msg::QueryMsg::Hooks {})
+        .unwrap();
+    assert!(res.hooks.is_empty());
+}
+
+#[test]
+fn test_max_hooks() {
+    let mut router = custom_mock_app();
+    let (deployer, _rob, _steve) = setup_accounts(&mut router).unwrap();
+    let (native_vault, _cw20_ucosm, _cw20_uusd) = setup_contracts(&mut router, &deployer).unwrap();
+
+    for i in 0..crate::state::MAX_HOOKS {
+        let msg = crate::msg::ExecuteMsg::AddHook {
+            addr: format!("hook{}", i),
+        };
+        router
+            .execute_contract(deployer.clone(), native_vault.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    let msg = crate::msg::ExecuteMsg::AddHook {
+        addr: "onehooktoomany".to_string(),
+    };
+    let err = router
+        .execute_contract(deployer, native_vault, &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::TooManyHooks(_)
+    ));
+}
+
+#[test]
+fn test_sudo_force_pause() {
+    let mut router = custom_mock_app();
+    let (deployer, rob, _steve) = setup_accounts(&mut router).unwrap();
+    let (native_vault, _cw20_ucosm, _cw20_uusd) = setup_contracts(&mut router, &deployer).unwrap();
+
+    // Governance pauses the vault
+    let res = router
+        .wasm_sudo(native_vault.clone(), &SudoMsg::ForcePause { paused: true })
+        .unwrap();
+    assert!(res.has_event(&Event::new("wasm").add_attribute("sudo_action", "force_pause")));
+
+    let msg = crate::msg::ExecuteMsg::Bond {
+        denom: UUSD_DENOM.to_string(),
+        amount: Uint128::new(1_000),
+    };
+    let err = router
+        .execute_contract(
+            rob.clone(),
+            native_vault.clone(),
+            &msg,
+            &coins(1_000, UUSD_DENOM),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Paused {}
+    ));
+
+    // Governance resumes the vault
+    router
+        .wasm_sudo(native_vault.clone(), &SudoMsg::ForcePause { paused: false })
+        .unwrap();
+    router
+        .execute_contract(rob, native_vault, &msg, &coins(1_000, UUSD_DENOM))
+        .unwrap();
+}
+
+#[test]
+fn test_sudo_set_admin() {
+    let mut router = custom_mock_app();
+    let (deployer, _rob, steve) = setup_accounts(&mut router).unwrap();
+    let (native_vault, _cw20_ucosm, _cw20_uusd) = setup_contracts(&mut router, &deployer).unwrap();
+
+    let res = router
+        .wasm_sudo(
+            native_vault.clone(),
+            &SudoMsg::SetAdmin {
+                admin: steve.to_string(),
+            },
+        )
+        .unwrap();
+    assert!(res.has_event(&Event::new("wasm").add_attribute("sudo_action", "set_admin")));
+
+    let config: Config = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &crate::msg::QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.admin, steve);
+
+    // The previous admin lost its rights
+    let msg = crate::msg::ExecuteMsg::AddHook {
+        addr: "hook".to_string(),
+    };
+    router
+        .execute_contract(deployer, native_vault.clone(), &msg, &[])
+        .unwrap_err();
+    router
+        .execute_contract(steve, native_vault, &msg, &[])
+        .unwrap();
+}
+
+#[test]
+fn test_sudo_remove_vault() {
+    let mut router = custom_mock_app();
+    let (deployer, _rob, _steve) = setup_accounts(&mut router).unwrap();
+    let (native_vault, _cw20_ucosm, _cw20_uusd) = setup_contracts(&mut router, &deployer).unwrap();
+
+    let res = router
+        .wasm_sudo(
+            native_vault.clone(),
+            &SudoMsg::RemoveVault {
+                denom: UUSD_DENOM.to_string(),
+            },
+        )
+        .unwrap();
+    assert!(res.has_event(&Event::new("wasm").add_attribute("sudo_action", "remove_vault")));
+
+    let res: DenomResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &crate::msg::QueryMsg::DenomList {})
+        .unwrap();
+    assert_eq!(res.denoms, vec![NATIVE_DENOM.to_string()]);
+
+    // Removing an unknown vault fails
+    let err = router
+        .wasm_sudo(
+            native_vault,
+            &SudoMsg::RemoveVault {
+                denom: UUSD_DENOM.to_string(),
+            },
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::VaultDoesNotExist(_)
+    ));
+}
+
+#[test]
+fn test_simulate_bond_and_unbond() {
+    let mut router = custom_mock_app();
+    let (deployer, rob, _steve) = setup_accounts(&mut router).unwrap();
+    let (native_vault, _cw20_ucosm, _cw20_uusd) = setup_contracts(&mut router, &deployer).unwrap();
+
+    let query = crate::msg::QueryMsg::SimulateBond {
+        denom: UUSD_DENOM.to_string(),
+        amount: Uint128::new(1_000),
+    };
+    let res: SimulateBondResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &query)
+        .unwrap();
+    assert_eq!(
+        res,
+        SimulateBondResponse {
+            receipt_amount: Uint128::new(1_000),
+            dust: Uint128::zero(),
+            limit: None,
+        }
+    );
+
+    let query = crate::msg::QueryMsg::SimulateUnbond {
+        denom: UUSD_DENOM.to_string(),
+        amount: Uint128::zero(),
+    };
+    let res: SimulateUnbondResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &query)
+        .unwrap();
+    assert_eq!(res.limit, Some(VaultLimit::Minimum));
+
+    // Both the simulation and the execution report the pause
+    router
+        .wasm_sudo(native_vault.clone(), &SudoMsg::ForcePause { paused: true })
+        .unwrap();
+    let query = crate::msg::QueryMsg::SimulateUnbond {
+        denom: UUSD_DENOM.to_string(),
+        amount: Uint128::new(1_000),
+    };
+    let res: SimulateUnbondResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &query)
+        .unwrap();
+    assert_eq!(res.limit, Some(VaultLimit::Paused));
+
+    let msg = crate::msg::ExecuteMsg::Unbond {
+        denom: UUSD_DENOM.to_string(),
+        amount: Uint128::new(1_000),
+    };
+    let err = router
+        .execute_contract(rob, native_vault, &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Paused {}
+    ));
+}
+
+#[test]
+fn test_simulate_bond_cap() {
+    let mut router = custom_mock_app();
+    let (deployer, rob, _steve) = setup_accounts(&mut router).unwrap();
+    let (native_vault, _cw20_ucosm, _cw20_uusd) = setup_contracts(&mut router, &deployer).unwrap();
+
+    // Instantiate a capped cw20 ucosm contract and use it as the ucosm vault
+    let cw20_code_id = router.store_code(contract_cw20_base());
+    let instantiate_msg = cw20_base::msg::InstantiateMsg {
+        name: "ucosm".to_string(),
+        symbol: "ATOM".to_string(),
+        decimals: 6,
+        initial_balances: vec![],
+        marketing: None,
+        mint: Some(cw20::MinterResponse {
+            minter: native_vault.to_string(),
+            cap: Some(Uint128::new(500)),
+        }),
+    };
+    let cw20_capped = router
+        .instantiate_contract(
+            cw20_code_id,
+            deployer.clone(),
+            &instantiate_msg,
+            &[],
+            "capped",
+            None,
+        )
+        .unwrap();
+    let msg = crate::msg::ExecuteMsg::AddVault {
+        denom: NATIVE_DENOM.to_string(),
+        address: cw20_capped.to_string(),
+        native_decimals: 6,
+    };
+    router
+        .execute_contract(deployer, native_vault.clone(), &msg, &[])
+        .unwrap();
+
+    let query = crate::msg::QueryMsg::SimulateBond {
+        denom: NATIVE_DENOM.to_string(),
+        amount: Uint128::new(1_000),
+    };
+    let res: SimulateBondResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &query)
+        .unwrap();
+    assert_eq!(res.limit, Some(VaultLimit::Cap));
+
+    let msg = crate::msg::ExecuteMsg::Bond {
+        denom: NATIVE_DENOM.to_string(),
+        amount: Uint128::new(1_000),
+    };
+    let err = router
+        .execute_contract(rob, native_vault, &msg, &coins(1_000, NATIVE_DENOM))
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::CapExceeded {}
+    ));
+}
+
+#[test]
+fn test_history() {
+    let mut router = custom_mock_app();
+    let (deployer, rob, steve) = setup_accounts(&mut router).unwrap();
+    let (native_vault, _cw20_ucosm, _cw20_uusd) = setup_contracts(&mut router, &deployer).unwrap();
+
+    // Rob bonds twice and Steve once
+    for (user, amount) in [(&rob, 100u128), (&steve, 200), (&rob, 300)] {
+        let msg = crate::msg::ExecuteMsg::Bond {
+            denom: UUSD_DENOM.to_string(),
+            amount: Uint128::new(amount),
+        };
+        router
+            .execute_contract(
+                user.clone(),
+                native_vault.clone(),
+                &msg,
+                &coins(amount, UUSD_DENOM),
+            )
+            .unwrap();
+    }
+
+    let query = crate::msg::QueryMsg::UserHistory {
+        owner: rob.to_string(),
+        start_after: None,
+        limit: None,
+    };
+    let res: HistoryResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &query)
+        .unwrap();
+    assert_eq!(res.actions.len(), 2);
+    assert_eq!(res.actions[0].id, 0);
+    assert_eq!(res.actions[0].kind, ActionKind::Bond);
+    assert_eq!(res.actions[0].amount, Uint128::new(100));
+    assert_eq!(res.actions[1].id, 2);
+    assert_eq!(res.actions[1].amount, Uint128::new(300));
+
+    // Paginate over the denom history
+    let query = crate::msg::QueryMsg::DenomHistory {
+        denom: UUSD_DENOM.to_string(),
+        start_after: Some(0),
+        limit: Some(1),
+    };
+    let res: HistoryResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &query)
+        .unwrap();
+    assert_eq!(res.actions.len(), 1);
+    assert_eq!(res.actions[0].user, steve);
+
+    // Bound the history to two actions, the oldest ones are pruned
+    let msg = crate::msg::ExecuteMsg::UpdateConfig {
+        admin: None,
+        history_limit: Some(2),
+    };
+    let err = router
+        .execute_contract(rob, native_vault.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized(_)
+    ));
+    router
+        .execute_contract(deployer, native_vault.clone(), &msg, &[])
+        .unwrap();
+    let msg = crate::msg::ExecuteMsg::Bond {
+        denom: UUSD_DENOM.to_string(),
+        amount: Uint128::new(400),
+    };
+    router
+        .execute_contract(steve, native_vault.clone(), &msg, &coins(400, UUSD_DENOM))
+        .unwrap();
+
+    let query = crate::msg::QueryMsg::DenomHistory {
+        denom: UUSD_DENOM.to_string(),
+        start_after: None,
+        limit: None,
+    };
+    let res: HistoryResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault, &query)
+        .unwrap();
+    let ids: Vec<u64> = res.actions.iter().map(|action| action.id).collect();
+    assert_eq!(ids, vec![2, 3]);
+}
+
+// Instantiates a cw20 with the given decimals and uses it as the vault of the given denom
+fn setup_vault_with_decimals(
+    router: &mut App,
+    deployer: &Addr,
+    native_vault: &Addr,
+    denom: &str,
+    decimals: u8,
+) -> Addr {
+    let cw20_code_id = router.store_code(contract_cw20_base());
+    let instantiate_msg = cw20_base::msg::InstantiateMsg {
+        name: denom.to_string(),
+        symbol: "SCALED".to_string(),
+        decimals,
+        initial_balances: vec![],
+        marketing: None,
+        mint: Some(cw20::MinterResponse {
+            minter: native_vault.to_string(),
+            cap: None,
+        }),
+    };
+    let cw20_scaled = router
+        .instantiate_contract(
+            cw20_code_id,
+            deployer.clone(),
+            &instantiate_msg,
+            &[],
+            "scaled",
+            None,
+        )
+        .unwrap();
+
+    let msg = crate::msg::ExecuteMsg::AddVault {
+        denom: denom.to_string(),
+        address: cw20_scaled.to_string(),
+        native_decimals: 6,
+    };
+    router
+        .execute_contract(deployer.clone(), native_vault.clone(), &msg, &[])
+        .unwrap();
+
+    cw20_scaled
+}
+
+#[test]
+fn test_decimals_scale_up() {
+    let mut router = custom_mock_app();
+    let (deployer, rob, _steve) = setup_accounts(&mut router).unwrap();
+    let (native_vault, _cw20_ucosm, _cw20_uusd) = setup_contracts(&mut router, &deployer).unwrap();
+    let cw20_scaled =
+        setup_vault_with_decimals(&mut router, &deployer, &native_vault, NATIVE_DENOM, 18);
+
+    // 1_000 native units with 6 decimals are worth 10^15 receipt units with 18 decimals
+    let msg = crate::msg::ExecuteMsg::Bond {
+        denom: NATIVE_DENOM.to_string(),
+        amount: Uint128::new(1_000),
+    };
+    router
+        .execute_contract(
+            rob.clone(),
+            native_vault.clone(),
+            &msg,
+            &coins(1_000, NATIVE_DENOM),
+        )
+        .unwrap();
+    let res: cw20::BalanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            cw20_scaled.clone(),
+            &cw20::Cw20QueryMsg::Balance {
+                address: rob.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.balance, Uint128::new(1_000_000_000_000_000));
+
+    // Only the receipts converting exactly into native units are burned
+    let amount = Uint128::new(400_000_000_000_123);
+    let query = crate::msg::QueryMsg::SimulateUnbond {
+        denom: NATIVE_DENOM.to_string(),
+        amount,
+    };
+    let res: SimulateUnbondResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &query)
+        .unwrap();
+    assert_eq!(
+        res,
+        SimulateUnbondResponse {
+            burn_amount: Uint128::new(400_000_000_000_000),
+            native_amount: Uint128::new(400),
+            limit: None,
+        }
+    );
+
+    let msg = cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+        spender: native_vault.to_string(),
+        amount,
+        expires: None,
+    };
+    router
+        .execute_contract(rob.clone(), cw20_scaled.clone(), &msg, &[])
+        .unwrap();
+    let msg = crate::msg::ExecuteMsg::Unbond {
+        denom: NATIVE_DENOM.to_string(),
+        amount,
+    };
+    router
+        .execute_contract(rob.clone(), native_vault.clone(), &msg, &[])
+        .unwrap();
+
+    // The history keeps the settled amounts
+    let query = crate::msg::QueryMsg::UserHistory {
+        owner: rob.to_string(),
+        start_after: Some(0),
+        limit: None,
+    };
+    let res: HistoryResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault, &query)
+        .unwrap();
+    assert_eq!(res.actions[0].kind, ActionKind::Unbond);
+    assert_eq!(res.actions[0].amount, Uint128::new(400));
+    assert_eq!(
+        res.actions[0].receipt_amount,
+        Uint128::new(400_000_000_000_000)
+    );
+
+    let res: cw20::BalanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            cw20_scaled,
+            &cw20::Cw20QueryMsg::Balance {
+                address: rob.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.balance, Uint128::new(600_000_000_000_000));
+    let balance = router.wrap().query_balance(rob, NATIVE_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(INITIAL_BALANCE - 600));
+}
+
+#[test]
+fn test_decimals_scale_down() {
+    let mut router = custom_mock_app();
+    let (deployer, rob, _steve) = setup_accounts(&mut router).unwrap();
+    let (native_vault, _cw20_ucosm, _cw20_uusd) = setup_contracts(&mut router, &deployer).unwrap();
+    let cw20_scaled =
+        setup_vault_with_decimals(&mut router, &deployer, &native_vault, NATIVE_DENOM, 2);
+
+    // 12_345 native units with 6 decimals are worth 1 receipt unit with 2 decimals
+    let query = crate::msg::QueryMsg::SimulateBond {
+        denom: NATIVE_DENOM.to_string(),
+        amount: Uint128::new(12_345),
+    };
+    let res: SimulateBondResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &query)
+        .unwrap();
+    assert_eq!(
+        res,
+        SimulateBondResponse {
+            receipt_amount: Uint128::new(1),
+            dust: Uint128::new(2_345),
+            limit: None,
+        }
+    );
+
+    let msg = crate::msg::ExecuteMsg::Bond {
+        denom: NATIVE_DENOM.to_string(),
+        amount: Uint128::new(12_345),
+    };
+    router
+        .execute_contract(
+            rob.clone(),
+            native_vault.clone(),
+            &msg,
+            &coins(12_345, NATIVE_DENOM),
+        )
+        .unwrap();
+
+    let res: cw20::BalanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            cw20_scaled.clone(),
+            &cw20::Cw20QueryMsg::Balance {
+                address: rob.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.balance, Uint128::new(1));
+
+    let query = crate::msg::QueryMsg::VaultInfo {
+        denom: NATIVE_DENOM.to_string(),
+    };
+    let res: VaultInfoResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &query)
+        .unwrap();
+    assert_eq!(
+        res,
+        VaultInfoResponse {
+            address: cw20_scaled.to_string(),
+            native_decimals: 6,
+            receipt_decimals: 2,
+            dust: Uint128::new(2_345),
+        }
+    );
+
+    // Less than one receipt unit hits the minimum
+    let query = crate::msg::QueryMsg::SimulateBond {
+        denom: NATIVE_DENOM.to_string(),
+        amount: Uint128::new(9_999),
+    };
+    let res: SimulateBondResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &query)
+        .unwrap();
+    assert_eq!(res.limit, Some(VaultLimit::Minimum));
+
+    // A vault added again does not inherit the dust of the removed one
+    router
+        .wasm_sudo(
+            native_vault.clone(),
+            &SudoMsg::RemoveVault {
+                denom: NATIVE_DENOM.to_string(),
+            },
+        )
+        .unwrap();
+    let msg = crate::msg::ExecuteMsg::AddVault {
+        denom: NATIVE_DENOM.to_string(),
+        address: cw20_scaled.to_string(),
+        native_decimals: 6,
+    };
+    router
+        .execute_contract(deployer, native_vault.clone(), &msg, &[])
+        .unwrap();
+    let query = crate::msg::QueryMsg::VaultInfo {
+        denom: NATIVE_DENOM.to_string(),
+    };
+    let res: VaultInfoResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault, &query)
+        .unwrap();
+    assert_eq!(res.dust, Uint128::zero());
+}
+
+#[test]
+fn test_basket() {
+    let mut router = custom_mock_app();
+    let (deployer, rob, _steve) = setup_accounts(&mut router).unwrap();
+    let (native_vault, _cw20_ucosm, _cw20_uusd) = setup_contracts(&mut router, &deployer).unwrap();
+
+    // Instantiate the index receipt with native_vault as the minter
+    let cw20_code_id = router.store_code(contract_cw20_base());
+    let instantiate_msg = cw20_base::msg::InstantiateMsg {
+        name: "index".to_string(),
+        symbol: "INDEX".to_string(),
+        decimals: 6,
+        initial_balances: vec![],
+        marketing: None,
+        mint: Some(cw20::MinterResponse {
+            minter: native_vault.to_string(),
+            cap: None,
+        }),
+    };
+    let cw20_index = router
+        .instantiate_contract(
+            cw20_code_id,
+            deployer.clone(),
+            &instantiate_msg,
+            &[],
+            "index",
+            None,
+        )
+        .unwrap();
+
+    // Only the admin can set the basket
+    let msg = crate::msg::ExecuteMsg::SetBasket {
+        receipt: cw20_index.to_string(),
+        components: vec![
+            BasketComponent {
+                denom: NATIVE_DENOM.to_string(),
+                weight: Uint128::new(2),
+            },
+            BasketComponent {
+                denom: UUSD_DENOM.to_string(),
+                weight: Uint128::new(3),
+            },
+        ],
+    };
+    router
+        .execute_contract(rob.clone(), native_vault.clone(), &msg, &[])
+        .unwrap_err();
+    router
+        .execute_contract(deployer.clone(), native_vault.clone(), &msg, &[])
+        .unwrap();
+
+    let res: BasketResponse = router
+        .wrap()
+        .query_wasm_smart(
+            native_vault.clone(),
+            &crate::msg::QueryMsg::BasketComposition {},
+        )
+        .unwrap();
+    assert_eq!(
+        res.components,
+        vec![Coin::new(2, NATIVE_DENOM), Coin::new(3, UUSD_DENOM)]
+    );
+
+    // 100 ucosm and 120 uusd make 40 units, the 20 extra ucosm are refunded
+    let funds = vec![Coin::new(100, NATIVE_DENOM), Coin::new(120, UUSD_DENOM)];
+    router
+        .execute_contract(
+            rob.clone(),
+            native_vault.clone(),
+            &crate::msg::ExecuteMsg::BondBasket {},
+            &funds,
+        )
+        .unwrap();
+    let res: cw20::BalanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            cw20_index.clone(),
+            &cw20::Cw20QueryMsg::Balance {
+                address: rob.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.balance, Uint128::new(40));
+    let balance = router.wrap().query_balance(&rob, NATIVE_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(INITIAL_BALANCE - 80));
+    let balance = router.wrap().query_balance(&rob, UUSD_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(INITIAL_BALANCE - 120));
+
+    // Unbonding 10 units returns every component pro rata
+    let msg = cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+        spender: native_vault.to_string(),
+        amount: Uint128::new(10),
+        expires: None,
+    };
+    router
+        .execute_contract(rob.clone(), cw20_index.clone(), &msg, &[])
+        .unwrap();
+    let msg = crate::msg::ExecuteMsg::UnbondBasket {
+        amount: Uint128::new(10),
+    };
+    router
+        .execute_contract(rob.clone(), native_vault.clone(), &msg, &[])
+        .unwrap();
+    let balance = router.wrap().query_balance(&rob, NATIVE_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(INITIAL_BALANCE - 60));
+    let balance = router.wrap().query_balance(&rob, UUSD_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(INITIAL_BALANCE - 90));
+
+    // Basket bond and unbond are recorded for every component
+    let query = crate::msg::QueryMsg::UserHistory {
+        owner: rob.to_string(),
+        start_after: None,
+        limit: None,
+    };
+    let res: HistoryResponse = router
+        .wrap()
+        .query_wasm_smart(native_vault.clone(), &query)
+        .unwrap();
+    let actions: Vec<(ActionKind, String, Uint128)> = res
+        .actions
+        .into_iter()
+        .map(|action| (action.kind, action.denom, action.amount))
+        .collect();
+    assert_eq!(
+        actions,
+        vec![
+            (
+                ActionKind::BondBasket,
+                NATIVE_DENOM.to_string(),
+                Uint128::new(80)
+            ),
+            (
+                ActionKind::BondBasket,
+                UUSD_DENOM.to_string(),
+                Uint128::new(120)
+            ),
+            (
+                ActionKind::UnbondBasket,
+                NATIVE_DENOM.to_string(),
+                Uint128::new(20)
+            ),
+            (
+                ActionKind::UnbondBasket,
+                UUSD_DENOM.to_string(),
+                Uint128::new(30)
+            ),
+        ]
+    );
+
+    // The weights cannot change while receipts are outstanding
+    let msg = crate::msg::ExecuteMsg::SetBasket {
+        receipt: cw20_index.to_string(),
+        components: vec![BasketComponent {
+            denom: NATIVE_DENOM.to_string(),
+            weight: Uint128::new(1),
+        }],
+    };
+    let err = router
+        .execute_contract(deployer, native_vault.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidBasket(_)
+    ));
+
+    // Coins outside of the basket are rejected
+    router
+        .sudo(CwSudoMsg::Bank(BankSudo::Mint {
+            to_address: rob.to_string(),
+            amount: coins(100, "uother"),
+        }))
+        .unwrap();
+    let err = router
+        .execute_contract(
+            rob,
+            native_vault,
+            &crate::msg::ExecuteMsg::BondBasket {},
+            &[Coin::new(100, NATIVE_DENOM), Coin::new(100, "uother")],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::PaymentError(_)
+    ));
+}
@@ -1,10 +1,24 @@
-use crate::msg::{BalanceResponse, DenomResponse, QueryMsg};
-use crate::state::{Config, CONFIG, VAULT_ADDRESSES};
+use crate::helpers::{calculate_bond, calculate_unbond};
+use crate::msg::{
+    BalanceResponse, BasketResponse, DenomResponse, HistoryResponse, QueryMsg,
+    SimulateBondResponse, SimulateUnbondResponse, VaultInfoResponse,
+};
+use crate::state::{
+    history, Config, VaultDecimals, BASKET, CONFIG, DUST, HOOKS, VAULT_ADDRESSES, VAULT_DECIMALS,
+};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Addr, Binary, Deps, Env, Order, QueryRequest, StdResult, WasmQuery};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Coin, Deps, Env, Order, QueryRequest, StdError, StdResult, Uint128,
+    WasmQuery,
+};
 use cw20::BalanceResponse as Cw20BalanceResponse;
-use cw20::Cw20QueryMsg;
+use cw20::{Cw20QueryMsg, TokenInfoResponse};
+use cw_storage_plus::Bound;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
@@ -18,6 +32,25 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::VaultAddress { denom } => to_binary(&query_vault_address(deps, denom)?),
         QueryMsg::DenomList {} => to_binary(&query_denom_list(deps)?),
+        QueryMsg::VaultInfo { denom } => to_binary(&query_vault_info(deps, denom)?),
+        QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
+        QueryMsg::BasketComposition {} => to_binary(&query_basket_composition(deps)?),
+        QueryMsg::SimulateBond { denom, amount } => {
+            to_binary(&query_simulate_bond(deps, denom, amount)?)
+        }
+        QueryMsg::SimulateUnbond { denom, amount } => {
+            to_binary(&query_simulate_unbond(deps, denom, amount)?)
+        }
+        QueryMsg::UserHistory {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_user_history(deps, owner, start_after, limit)?),
+        QueryMsg::DenomHistory {
+            denom,
+            start_after,
+            limit,
+        } => to_binary(&query_denom_history(deps, denom, start_after, limit)?),
     }
 }
 
@@ -43,6 +76,35 @@ fn query_vault_address(deps: Deps, denom: String) -> StdResult<Addr> {
     VAULT_ADDRESSES.load(deps.storage, denom)
 }
 
+/// Returns the decimals and the dust of the vault of the given denom
+fn query_vault_info(deps: Deps, denom: String) -> StdResult<VaultInfoResponse> {
+    let address = VAULT_ADDRESSES.load(deps.storage, denom.clone())?;
+    let dust = DUST
+        .may_load(deps.storage, denom.clone())?
+        .unwrap_or_default();
+
+    // Vaults added before decimals were recorded are one to one
+    let decimals = match VAULT_DECIMALS.may_load(deps.storage, denom)? {
+        Some(decimals) => decimals,
+        None => {
+            let token_info: TokenInfoResponse = deps
+                .querier
+                .query_wasm_smart(&address, &Cw20QueryMsg::TokenInfo {})?;
+            VaultDecimals {
+                native: token_info.decimals,
+                receipt: token_info.decimals,
+            }
+        }
+    };
+
+    Ok(VaultInfoResponse {
+        address: address.to_string(),
+        native_decimals: decimals.native,
+        receipt_decimals: decimals.receipt,
+        dust,
+    })
+}
+
 /// Returns all supported denoms
 fn query_denom_list(deps: Deps) -> StdResult<DenomResponse> {
     // Get all keys from VAULT_ADDRESSES
@@ -50,10 +112,104 @@ fn query_denom_list(deps: Deps) -> StdResult<DenomResponse> {
         .keys(deps.storage, None, None, Order::Ascending)
         .map(|item| item.unwrap_or("Error while getting keys".to_string()))
         .collect();
-    return Ok(DenomResponse { denoms });
+    Ok(DenomResponse { denoms })
 }
 
 /// Returns the config of the contract
 fn query_config(deps: Deps) -> StdResult<Config> {
     CONFIG.load(deps.storage)
 }
+
+/// Returns the native coins backing one basket receipt unit
+fn query_basket_composition(deps: Deps) -> StdResult<BasketResponse> {
+    let basket = BASKET.load(deps.storage)?;
+
+    Ok(BasketResponse {
+        receipt: basket.receipt.to_string(),
+        components: basket
+            .components
+            .into_iter()
+            .map(|c| Coin {
+                denom: c.denom,
+                amount: c.weight,
+            })
+            .collect(),
+    })
+}
+
+/// Returns the receipt minted when bonding, using the same calculation as Bond
+fn query_simulate_bond(
+    deps: Deps,
+    denom: String,
+    amount: Uint128,
+) -> StdResult<SimulateBondResponse> {
+    let calculation =
+        calculate_bond(deps, &denom, amount).map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(SimulateBondResponse {
+        receipt_amount: calculation.receipt_amount,
+        dust: calculation.dust,
+        limit: calculation.limit,
+    })
+}
+
+/// Returns the native tokens returned when unbonding, using the same calculation as Unbond
+fn query_simulate_unbond(
+    deps: Deps,
+    denom: String,
+    amount: Uint128,
+) -> StdResult<SimulateUnbondResponse> {
+    let calculation =
+        calculate_unbond(deps, &denom, amount).map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(SimulateUnbondResponse {
+        burn_amount: calculation.burn_amount,
+        native_amount: calculation.native_amount,
+        limit: calculation.limit,
+    })
+}
+
+/// Returns the actions of the given owner, oldest first
+fn query_user_history(
+    deps: Deps,
+    owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<HistoryResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let actions = history()
+        .idx
+        .user
+        .prefix(owner)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, action)| action))
+        .collect::<StdResult<_>>()?;
+
+    Ok(HistoryResponse { actions })
+}
+
+/// Returns the actions on the given denom, oldest first
+fn query_denom_history(
+    deps: Deps,
+    denom: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<HistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let actions = history()
+        .idx
+        .denom
+        .prefix(denom)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, action)| action))
+        .collect::<StdResult<_>>()?;
+
+    Ok(HistoryResponse { actions })
+}
@@ -1,5 +1,6 @@
-use cosmwasm_std::Addr;
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Timestamp, Uint128};
+use cw_controllers::Hooks;
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -7,6 +8,12 @@ use serde::{Deserialize, Serialize};
 pub struct Config {
     /// The address of the admin that can create new vaults
     pub admin: Addr,
+    /// Whether bond and unbond are halted by governance
+    #[serde(default)]
+    pub paused: bool,
+    /// Maximum number of actions kept in the history, the oldest are pruned first
+    #[serde(default)]
+    pub history_limit: Option<u64>,
 }
 
 /// The config of the contract
@@ -14,3 +21,100 @@ pub const CONFIG: Item<Config> = Item::new("config");
 
 /// Links the native denom to the corresponding cw20 vault
 pub const VAULT_ADDRESSES: Map<String, Addr> = Map::new("vault_addresses");
+
+/// Decimals of a native denom and of its cw20 receipt
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VaultDecimals {
+    pub native: u8,
+    pub receipt: u8,
+}
+
+/// Links the native denom to the decimals of both sides of its vault
+pub const VAULT_DECIMALS: Map<String, VaultDecimals> = Map::new("vault_decimals");
+
+/// Native amounts kept by the vault because they were too small to mint a receipt unit
+pub const DUST: Map<String, Uint128> = Map::new("dust");
+
+/// A native denom of the basket and its amount per receipt unit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BasketComponent {
+    pub denom: String,
+    pub weight: Uint128,
+}
+
+/// Several native denoms backing a single cw20 receipt
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Basket {
+    pub receipt: Addr,
+    pub components: Vec<BasketComponent>,
+}
+
+/// The basket backing the index receipt, if any
+pub const BASKET: Item<Basket> = Item::new("basket");
+
+/// Maximum number of hook subscribers, keeps the gas of bond and unbond predictable
+pub const MAX_HOOKS: usize = 10;
+
+/// Contracts notified on every bond and unbond
+pub const HOOKS: Hooks = Hooks::new("hooks");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Bond,
+    Unbond,
+    BondBasket,
+    UnbondBasket,
+}
+
+/// An operation performed by a user
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Action {
+    pub id: u64,
+    pub user: Addr,
+    pub kind: ActionKind,
+    pub denom: String,
+    /// Native amount bonded or paid out
+    pub amount: Uint128,
+    /// Receipt amount minted or burned
+    #[serde(default)]
+    pub receipt_amount: Uint128,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+pub struct ActionIndexes<'a> {
+    pub user: MultiIndex<'a, Addr, Action, u64>,
+    pub denom: MultiIndex<'a, String, Action, u64>,
+}
+
+impl<'a> IndexList<Action> for ActionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Action>> + '_> {
+        let v: Vec<&dyn Index<Action>> = vec![&self.user, &self.denom];
+        Box::new(v.into_iter())
+    }
+}
+
+/// History of the actions, indexed by user and by denom
+pub fn history<'a>() -> IndexedMap<'a, u64, Action, ActionIndexes<'a>> {
+    let indexes = ActionIndexes {
+        user: MultiIndex::new(
+            |_pk, action| action.user.clone(),
+            "history",
+            "history__user",
+        ),
+        denom: MultiIndex::new(
+            |_pk, action| action.denom.clone(),
+            "history",
+            "history__denom",
+        ),
+    };
+    IndexedMap::new("history", indexes)
+}
+
+/// Maximum number of actions pruned per recorded action, keeps bond and unbond gas bounded
+/// when the history limit is lowered below a long history
+pub const MAX_PRUNED_PER_ACTION: u64 = 2;
+
+/// Id of the next action recorded in the history
+pub const NEXT_ACTION_ID: Item<u64> = Item::new("next_action_id");
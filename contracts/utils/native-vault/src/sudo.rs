@@ -0,0 +1,58 @@
+use crate::error::ContractError;
+use crate::msg::SudoMsg;
+use crate::state::{CONFIG, DUST, VAULT_ADDRESSES, VAULT_DECIMALS};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{DepsMut, Env, Response};
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::ForcePause { paused } => sudo_force_pause(deps, env, paused),
+        SudoMsg::SetAdmin { admin } => sudo_set_admin(deps, env, admin),
+        SudoMsg::RemoveVault { denom } => sudo_remove_vault(deps, env, denom),
+    }
+}
+
+/// Halt or resume bond and unbond
+pub fn sudo_force_pause(deps: DepsMut, _env: Env, paused: bool) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    config.paused = paused;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo")
+        .add_attribute("sudo_action", "force_pause")
+        .add_attribute("paused", paused.to_string()))
+}
+
+/// Replace the admin without its signature
+pub fn sudo_set_admin(deps: DepsMut, _env: Env, admin: String) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    config.admin = deps.api.addr_validate(&admin)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo")
+        .add_attribute("sudo_action", "set_admin")
+        .add_attribute("admin", admin))
+}
+
+/// Unlink the cw20 vault of the given denom
+pub fn sudo_remove_vault(
+    deps: DepsMut,
+    _env: Env,
+    denom: String,
+) -> Result<Response, ContractError> {
+    if !VAULT_ADDRESSES.has(deps.storage, denom.clone()) {
+        return Err(ContractError::VaultDoesNotExist(denom));
+    }
+    VAULT_ADDRESSES.remove(deps.storage, denom.clone());
+    VAULT_DECIMALS.remove(deps.storage, denom.clone());
+    DUST.remove(deps.storage, denom.clone());
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo")
+        .add_attribute("sudo_action", "remove_vault")
+        .add_attribute("denom", denom))
+}
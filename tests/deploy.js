@@ -132,6 +132,7 @@ const executeAddAtomResult = await client.execute(
     add_vault: {
         denom: "ucosm",
         address: instantiateAtomCW20Result.contractAddress,
+        native_decimals: 6,
     },
 }, "auto");
 
@@ -143,6 +144,7 @@ const executeAddHusdResult = await client.execute(
     add_vault: {
         denom: "uusd",
         address: instantiateHusdCW20Result.contractAddress,
+        native_decimals: 6,
     },
 }, "auto");
 